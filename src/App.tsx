@@ -2,7 +2,14 @@ import { useState, useEffect } from "react";
 import "./App.css";
 
 import * as wasm from "../wasm-lib/pkg/wasm_lib";
-import { DEFAULT_EQS, Equation, detectVars, formatComplex } from "./equation";
+import {
+    DEFAULT_EQS,
+    Equation,
+    formatComplex,
+    formatQuantity,
+    parseParams,
+    previewEquation,
+} from "./equation";
 import { PositionedError } from "../wasm-lib/pkg/wasm_lib";
 
 const DESCRIPTIONS = [
@@ -14,6 +21,14 @@ const DESCRIPTIONS = [
             variable is picked, after which this iterative method is applied\
             until convergence.",
             "You can control the amount of attempts to find a solution and the number of iterations per attempt.",
+            "Systems where every equation is linear are solved directly instead.",
+            "With homotopy continuation, each attempt instead deforms the trivial\
+            system x = initial guess into yours, following the solution along the way.",
+            "The first attempt can start from the last solution instead, which\
+            usually finds the nearby solution after a small change to the known values.",
+            "For polynomial systems, every isolated solution can be found at\
+            once by following one path per solution of a simpler system with\
+            the same degrees.",
         ],
     },
     {
@@ -23,6 +38,9 @@ const DESCRIPTIONS = [
             input. The allowed naming scheme is any combination of\
             alphanumeric characters, underscores, and apostrophes, but must\
             not start with a number.",
+            "Variables given a known value are treated as constants instead of\
+            being solved for, so the same equations can be solved again for\
+            different inputs.",
         ],
     },
     {
@@ -31,47 +49,180 @@ const DESCRIPTIONS = [
             "Supported operators: + - * / ^ %",
             "Supported functions: " + wasm.funcs(),
             "Predefined constants: e, pi, i",
+            "Units go in brackets after a number, like 9.81 [m/s^2] or 30\
+            [min]. Values are converted to SI units, the units of every\
+            equation are checked to agree, and solutions are shown with the\
+            units they must have.",
             "Complex numbers are supported",
+            "Multivalued functions (ln, powers, roots and inverse functions) use\
+            the principal branch by default. A different branch can be picked,\
+            and odd roots of negative numbers can be made real, so that\
+            (-8)^(1/3) = -2.",
         ],
     },
 ];
 
 const App = () => {
     let [equations, setEquations] = useState([...DEFAULT_EQS]);
-    const flattenEqs = () => equations.flatMap(eq => [eq.left, eq.right]);
 
-    let [vars, setVars] = useState(detectVars(flattenEqs()));
+    let [params, setParams] = useState(parseParams(""));
+    const detectUnknowns = (): string[] =>
+        wasm
+            .variables(equations.map(eq => [eq.left, eq.right]))
+            .filter((v: string) => !(v in params));
 
+    let [vars, setVars] = useState(detectUnknowns());
+
+    // keep the last list while an equation is being typed and does not parse yet
     useEffect(() => {
-        setVars(detectVars(flattenEqs()));
-    }, [equations]);
+        try {
+            setVars(detectUnknowns());
+        } catch {}
+    }, [equations, params]);
 
     let [errMsg, setErrMsg] = useState("");
-    const EMPTY_SOLUTION: [string, [number, number]][] = [];
+    const EMPTY_WARNINGS: string[] = [];
+    let [warnings, setWarnings] = useState(EMPTY_WARNINGS);
+    const EMPTY_SOLUTION: [string, [number, number], string][] = [];
     let [solution, setSolution] = useState(EMPTY_SOLUTION);
+    const EMPTY_ROOTS: {
+        solution: [string, [number, number], string][];
+        residual: number;
+        condition: number;
+        regular: boolean;
+        paths: number;
+    }[] = [];
+    let [allRoots, setAllRoots] = useState(EMPTY_ROOTS);
 
     let [attempts, setAttempts] = useState(50);
     let [iterations, setIterations] = useState(1000);
+    let [realRoots, setRealRoots] = useState(false);
+    let [branch, setBranch] = useState(0);
+    let [homotopy, setHomotopy] = useState(false);
+    let [warmStart, setWarmStart] = useState(false);
+    let [expression, setExpression] = useState("");
 
-    const solve = () => {
+    const calculate = () => {
+        if (expression.trim() == "") {
+            return "";
+        }
+        try {
+            let [re, im] = wasm.evaluate(
+                expression,
+                { ...params, ...Object.fromEntries(solution) },
+                realRoots,
+                branch
+            );
+            return `= ${formatComplex(re, im)}`;
+        } catch (e) {
+            return e instanceof PositionedError ? e.msg : "";
+        }
+    };
+
+    const reset = () => {
         setErrMsg("");
+        setWarnings([]);
         setSolution([]);
+        setAllRoots([]);
         for (let eq of equations) {
             eq.err_left = false;
             eq.err_right = false;
         }
+    };
+
+    const showError = (e: unknown) => {
+        if (e instanceof PositionedError) {
+            setErrMsg(e.msg);
+            if (e.second) {
+                equations[e.eq].err_right = true;
+            } else {
+                equations[e.eq].err_left = true;
+            }
+        }
+    };
 
+    const solveAll = () => {
+        reset();
         try {
+            let result = wasm.solve_all(
+                equations.map(eq => [eq.left, eq.right]),
+                params,
+                Math.max(0, iterations),
+                realRoots,
+                branch
+            );
+            if (result === undefined) {
+                setErrMsg(
+                    "Finding all solutions needs as many polynomial equations as variables"
+                );
+            } else if (result.roots.length == 0) {
+                setErrMsg(`No solutions found on ${result.paths} paths`);
+            } else {
+                setAllRoots(result.roots);
+            }
+        } catch (e) {
+            showError(e);
+        }
+        setEquations([...equations]);
+    };
+
+    const solve = () => {
+        let previous = Object.fromEntries(solution);
+        reset();
+
+        try {
+            setWarnings(
+                wasm
+                    .analyze(
+                        equations.map(eq => [eq.left, eq.right]),
+                        params,
+                        realRoots,
+                        branch
+                    )
+                    .map((w: { msg: string }) => w.msg)
+            );
+
+            let linear = wasm.solve_linear(
+                equations.map(eq => [eq.left, eq.right]),
+                params,
+                realRoots,
+                branch
+            );
+            if (linear !== undefined) {
+                if (linear.kind == "none") {
+                    setErrMsg("The linear system has no solution");
+                } else {
+                    setSolution(linear.solution);
+                }
+                if (linear.kind == "infinite") {
+                    setErrMsg(
+                        `Infinitely many solutions, any multiple of ${linear.null_space
+                            .map(
+                                (v: [string, [number, number]][]) =>
+                                    "(" +
+                                    v
+                                        .map(([name, [re, im]]) =>
+                                            `${name} = ${formatComplex(re, im)}`
+                                        )
+                                        .join(", ") +
+                                    ")"
+                            )
+                            .join(" or ")} can be added to the solution below`
+                    );
+                }
+                setEquations([...equations]);
+                return;
+            }
+
             let found = false;
             for (let i = 0; i < attempts; i++) {
-                let initial = vars.flatMap(_ => [
-                    Math.random() * 100 - 50,
-                    Math.random() * 100 - 50,
-                ]);
-                let solution = wasm.solve(
+                let solution = (homotopy ? wasm.solve_homotopy : wasm.solve)(
                     equations.map(eq => [eq.left, eq.right]),
+                    params,
                     Math.max(0, iterations),
-                    new Float64Array(initial)
+                    warmStart && i == 0 ? previous : {},
+                    realRoots,
+                    branch
                 );
                 if (solution != undefined) {
                     found = true;
@@ -83,14 +234,7 @@ const App = () => {
                 setErrMsg("No solutions found");
             }
         } catch (e) {
-            if (e instanceof PositionedError) {
-                setErrMsg(e.msg);
-                if (e.second) {
-                    equations[e.eq].err_right = true;
-                } else {
-                    equations[e.eq].err_left = true;
-                }
-            }
+            showError(e);
         }
         setEquations([...equations]);
     };
@@ -163,6 +307,52 @@ const App = () => {
                         setIterations(parseInt(v.target.value));
                     }}
                 />
+                <br />
+                Real odd roots:{" "}
+                <input
+                    type="checkbox"
+                    defaultChecked={false}
+                    onChange={v => {
+                        setRealRoots(v.target.checked);
+                    }}
+                />{" "}
+                <br />
+                Branch:{" "}
+                <input
+                    type="number"
+                    step={1}
+                    defaultValue={0}
+                    onChange={v => {
+                        setBranch(parseInt(v.target.value) || 0);
+                    }}
+                />
+                <br />
+                Known values:{" "}
+                <input
+                    type="text"
+                    placeholder="g = 9.81, m = 2"
+                    onChange={v => {
+                        setParams(parseParams(v.target.value));
+                    }}
+                />
+                <br />
+                Homotopy continuation:{" "}
+                <input
+                    type="checkbox"
+                    defaultChecked={false}
+                    onChange={v => {
+                        setHomotopy(v.target.checked);
+                    }}
+                />
+                <br />
+                Start from the last solution:{" "}
+                <input
+                    type="checkbox"
+                    defaultChecked={false}
+                    onChange={v => {
+                        setWarmStart(v.target.checked);
+                    }}
+                />
                 <div className="solver">
                     <span>
                         <button
@@ -186,6 +376,11 @@ const App = () => {
                     ) : (
                         <></>
                     )}
+                    {warnings.map((w, i) => (
+                        <div key={i}>
+                            <span className="warningmsg">{w}</span>
+                        </div>
+                    ))}
                     <br />
 
                     {equations.map((eq, i) => (
@@ -195,7 +390,6 @@ const App = () => {
                                 onClick={() => {
                                     equations.splice(i, 1);
                                     setEquations([...equations]);
-                                    // setVars(detectVars(flattenEqs()));
                                 }}
                             >
                                 <span className="material-symbols-outlined">
@@ -211,7 +405,6 @@ const App = () => {
                                 onChange={v => {
                                     equations[i].left = v.target.value;
                                     setEquations([...equations]);
-                                    // setVars(detectVars(flattenEqs()));
                                 }}
                             />
                             <span> = </span>
@@ -224,23 +417,29 @@ const App = () => {
                                 onChange={v => {
                                     equations[i].right = v.target.value;
                                     setEquations([...equations]);
-                                    // setVars(detectVars(flattenEqs()));
                                 }}
                             />
+                            <span className="preview">
+                                {previewEquation(eq)}
+                            </span>
                         </div>
                     ))}
                 </div>
                 <button className="solve_button" onClick={solve}>
                     Solve
+                </button>{" "}
+                <button className="solve_button" onClick={solveAll}>
+                    Find all solutions
                 </button>
                 {solution.length != 0 ? (
                     <>
                         <h4>Solutions:</h4>
-                        {solution.map(([name, [re, im]], i) => (
+                        {solution.map(([name, [re, im], unit], i) => (
                             <div key={i}>
-                                <span className="solution">{`${name} = ${formatComplex(
+                                <span className="solution">{`${name} = ${formatQuantity(
                                     re,
-                                    im
+                                    im,
+                                    unit
                                 )}`}</span>
                                 <br />
                             </div>
@@ -249,6 +448,43 @@ const App = () => {
                 ) : (
                     <></>
                 )}
+                {allRoots.map((root, i) => (
+                    <div key={i}>
+                        <h4>
+                            Solution {i + 1} (residual{" "}
+                            {root.residual.toExponential(1)}, condition{" "}
+                            {root.condition.toExponential(1)}
+                            {root.regular
+                                ? ""
+                                : `, singular, reached by ${root.paths} paths`}
+                            ):
+                        </h4>
+                        {root.solution.map(([name, [re, im], unit], j) => (
+                            <div key={j}>
+                                <span className="solution">{`${name} = ${formatQuantity(
+                                    re,
+                                    im,
+                                    unit
+                                )}`}</span>
+                                <br />
+                            </div>
+                        ))}
+                    </div>
+                ))}
+                <br />
+                <h3>Calculator</h3>
+                <hr />
+                Known values and the solution can be used in the expression.
+                <br />
+                <input
+                    type="text"
+                    className="equation_input"
+                    placeholder="sin(pi/4)^2"
+                    onChange={v => {
+                        setExpression(v.target.value);
+                    }}
+                />{" "}
+                <span className="solution">{calculate()}</span>
             </div>
         </div>
     );
@@ -15,20 +15,24 @@ export const DEFAULT_EQS: Equation[] = [
     new Equation("a + b", "5"),
 ];
 
-const VAR_REGEX = /\b[A-Za-z_][A-Za-z0-9_']*(?!\s*\()\b/g;
-
-export const detectVars = (eqs: string[]) => {
-    let varSet = new Set<string>();
-    for (let eq of eqs) {
-        for (let match of eq.matchAll(VAR_REGEX)) {
-            let v = match[0];
-            if (!wasm.is_not_var(v)) {
-                varSet.add(match[0]);
-            }
+// Parses known values written as `g = 9.81, m = 2`
+export const parseParams = (text: string) => {
+    let params: { [name: string]: number } = {};
+    for (let part of text.split(",")) {
+        let [name, value] = part.split("=").map(s => s.trim());
+        if (name && value !== undefined && !isNaN(parseFloat(value))) {
+            params[name] = parseFloat(value);
         }
     }
+    return params;
+};
 
-    return [...varSet];
+export const previewEquation = (eq: Equation): string | undefined => {
+    try {
+        return wasm.preview([[eq.left, eq.right]])[0].text;
+    } catch {
+        return undefined;
+    }
 };
 
 export const formatComplex = (re: number, im: number) => {
@@ -43,3 +47,11 @@ export const formatComplex = (re: number, im: number) => {
     }
     return `${re} - ${-im}i`;
 };
+
+export const formatQuantity = (re: number, im: number, unit: string) => {
+    let value = formatComplex(re, im);
+    if (unit == "") {
+        return value;
+    }
+    return value.includes("i") ? `(${value}) ${unit}` : `${value} ${unit}`;
+};
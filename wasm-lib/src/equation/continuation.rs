@@ -0,0 +1,286 @@
+use nalgebra::{DMatrix, DVector};
+use num_complex::Complex64;
+
+use super::{
+    structure::Block,
+    system::{NewtonStats, System},
+};
+
+const INITIAL_STEP: f64 = 0.05;
+/// Longer steps make the predictor jump between paths that come close
+const MAX_STEP: f64 = 0.1;
+/// Systems with large coefficients move their paths away from the start quickly, which takes
+/// steps far shorter than the path's length
+const MIN_STEP: f64 = 1e-14;
+/// Paths that stall this close to their end are returned as they are
+const ENDGAME: f64 = 1e-4;
+/// Paths are watched for going to infinity once this close to their end
+const ENDGAME_START: f64 = 0.1;
+/// Factor by which paths in the endgame at most get closer to their end in one step, so their
+/// growth is measured over every decade of `1 - t`
+const ENDGAME_RATIO: f64 = 0.1;
+/// Paths this close to their end take their last step straight to it
+const ENDGAME_END: f64 = 1e-8;
+/// Paths to infinity grow like `(1 - t)^-order` near their end, while paths to a root stop
+/// growing however large it is. Paths growing faster than this order diverge.
+const DIVERGING_ORDER: f64 = 0.1;
+/// Decades of `1 - t` over which a path has to keep growing to diverge
+const DIVERGING_DECADES: usize = 2;
+const CORRECTOR_ITERS: usize = 5;
+const CORRECTOR_TOLERANCE: f64 = 1e-8;
+/// Largest corrector move relative to the predictor's
+const MAX_CORRECTION: f64 = 0.25;
+/// Successful steps in a row after which the step size is doubled
+const STEP_INCREASE_AFTER: usize = 3;
+/// Generic constant of the start homotopy, avoiding singular paths for almost every system
+const GAMMA_ANGLE: f64 = 0.8364;
+
+/// `H(x, t) = 0`, deforming a problem with a known solution at `t = 0` into the one at `t = 1`
+pub trait Homotopy {
+    fn eval(&self, x: &[Complex64], t: f64) -> DVector<Complex64>;
+    /// `∂H/∂x`
+    fn jacobian(&self, x: &[Complex64], t: f64) -> DMatrix<Complex64>;
+    /// `∂H/∂t`
+    fn dt(&self, x: &[Complex64], t: f64) -> DVector<Complex64>;
+}
+
+/// Least squares solution of `a x = b`
+pub fn solve_least_squares(
+    a: DMatrix<Complex64>,
+    b: &DVector<Complex64>,
+) -> Option<DVector<Complex64>> {
+    let x = if a.is_square() {
+        a.lu().solve(b)?
+    } else {
+        a.svd(true, true).solve(b, 1e-14).ok()?
+    };
+    x.iter().all(|v| v.is_finite()).then_some(x)
+}
+
+/// Newton iterations on `H(x, t) = 0` for a fixed `t`
+fn correct<H: Homotopy>(h: &H, x: &mut DVector<Complex64>, t: f64) -> bool {
+    for _ in 0..CORRECTOR_ITERS {
+        let Some(step) = solve_least_squares(h.jacobian(x.as_slice(), t), &h.eval(x.as_slice(), t))
+        else {
+            return false;
+        };
+        *x -= &step;
+        if step.norm() < CORRECTOR_TOLERANCE * (1.0 + x.norm()) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Follows the solution `x` of `H(x, 0) = 0` to `t = 1` with an Euler predictor and a Newton
+/// corrector, adapting the step size, or returns `None` if the path is lost or goes to infinity
+pub fn track<H: Homotopy>(h: &H, x: Vec<Complex64>) -> Option<Vec<Complex64>> {
+    let mut x = DVector::from_vec(x);
+    let (mut t, mut step, mut successes) = (0.0, INITIAL_STEP, 0);
+    // `1 - t` and the norm when the growth was last measured, and for how many decades in a
+    // row the path grew
+    let mut sample: Option<(f64, f64)> = None;
+    let mut growing = 0;
+
+    while t < 1.0 {
+        step = step.min(1.0 - t);
+        if (ENDGAME_END..=ENDGAME_START).contains(&(1.0 - t)) {
+            step = step.min((1.0 - t) * (1.0 - ENDGAME_RATIO));
+        }
+        let tangent = solve_least_squares(h.jacobian(x.as_slice(), t), &-h.dt(x.as_slice(), t))?;
+
+        let predicted = &x + tangent * Complex64::from(step);
+        let mut next = predicted.clone();
+        // a corrector moving far from the prediction may have landed on another path
+        if correct(h, &mut next, t + step)
+            && (&next - &predicted).norm() <= MAX_CORRECTION * (&predicted - &x).norm()
+        {
+            x = next;
+            t += step;
+            let remaining = 1.0 - t;
+            match sample {
+                _ if remaining > ENDGAME_START || remaining <= 0.0 => (),
+                None => sample = Some((remaining, x.norm())),
+                Some((last, norm)) if remaining <= last * ENDGAME_RATIO => {
+                    let order = (x.norm() / norm).ln() / (last / remaining).ln();
+                    if norm > 0.0 && order > DIVERGING_ORDER {
+                        growing += 1;
+                    } else {
+                        growing = 0;
+                    }
+                    if growing == DIVERGING_DECADES {
+                        return None;
+                    }
+                    sample = Some((remaining, x.norm()));
+                }
+                Some(_) => (),
+            }
+            successes += 1;
+            if successes == STEP_INCREASE_AFTER {
+                step = (step * 2.0).min(MAX_STEP);
+                successes = 0;
+            }
+        } else {
+            step /= 2.0;
+            successes = 0;
+            if step < MIN_STEP {
+                // paths meeting at a singular root cannot be followed all the way, but are
+                // close enough for the caller's Newton iterations
+                return (1.0 - t < ENDGAME).then(|| x.iter().copied().collect());
+            }
+        }
+    }
+    Some(x.iter().copied().collect())
+}
+
+/// The system with one variable moving linearly from `from` to `to`
+struct ParamHomotopy<'a> {
+    system: &'a System,
+    param: usize,
+    from: Complex64,
+    to: Complex64,
+    block: Block,
+}
+
+impl ParamHomotopy<'_> {
+    fn args(&self, x: &[Complex64], t: f64) -> Vec<Complex64> {
+        let mut args = vec![Complex64::default(); self.system.var_amount];
+        for (&var, v) in self.block.vars.iter().zip(x) {
+            args[var] = *v
+        }
+        args[self.param] = self.from + (self.to - self.from) * t;
+        args
+    }
+}
+
+impl Homotopy for ParamHomotopy<'_> {
+    fn eval(&self, x: &[Complex64], t: f64) -> DVector<Complex64> {
+        self.system.block_run(&self.args(x, t), &self.block)
+    }
+    fn jacobian(&self, x: &[Complex64], t: f64) -> DMatrix<Complex64> {
+        self.system.block_jacobian(&self.args(x, t), &self.block)
+    }
+    fn dt(&self, x: &[Complex64], t: f64) -> DVector<Complex64> {
+        let param_block = Block {
+            eqs: self.block.eqs.clone(),
+            vars: vec![self.param],
+        };
+        self.system
+            .block_jacobian(&self.args(x, t), &param_block)
+            .column(0)
+            * (self.to - self.from)
+    }
+}
+
+/// `t F(x) + (1 - t) γ (x - x₀)`, which starts at the solution `x₀`
+struct StartHomotopy<'a> {
+    system: &'a System,
+    start: DVector<Complex64>,
+    gamma: Complex64,
+}
+
+impl Homotopy for StartHomotopy<'_> {
+    fn eval(&self, x: &[Complex64], t: f64) -> DVector<Complex64> {
+        let start = (DVector::from_column_slice(x) - &self.start) * self.gamma;
+        self.system.run(x) * Complex64::from(t) + start * Complex64::from(1.0 - t)
+    }
+    fn jacobian(&self, x: &[Complex64], t: f64) -> DMatrix<Complex64> {
+        let n = self.system.var_amount;
+        self.system.jacobian(x) * Complex64::from(t)
+            + DMatrix::identity(n, n) * (self.gamma * (1.0 - t))
+    }
+    fn dt(&self, x: &[Complex64], _: f64) -> DVector<Complex64> {
+        self.system.run(x) - (DVector::from_column_slice(x) - &self.start) * self.gamma
+    }
+}
+
+impl System {
+    /// Solves the system for every value of variable `param`, starting from `initial` and then
+    /// following the solution from one value to the next, or starting from the last solution
+    /// where it cannot be followed. The other variables of unsolved values are `None`, and
+    /// `param` is left out of the solutions.
+    pub fn sweep(
+        &self,
+        param: usize,
+        values: &[Complex64],
+        initial: Vec<Complex64>,
+        iter: usize,
+    ) -> Vec<Option<Vec<Complex64>>> {
+        let block = Block {
+            eqs: (0..self.eqs.len()).collect(),
+            vars: (0..self.var_amount).filter(|v| *v != param).collect(),
+        };
+        if initial.len() < self.var_amount {
+            return vec![None; values.len()];
+        }
+        let mut out = vec![];
+        let mut last: Option<(Complex64, Vec<Complex64>)> = None;
+
+        for &value in values {
+            let tracked = last.as_ref().and_then(|(from, x)| {
+                let h = ParamHomotopy {
+                    system: self,
+                    param,
+                    from: *from,
+                    to: value,
+                    block: block.clone(),
+                };
+                track(&h, x.clone())
+            });
+
+            // Newton iterations start from the last solution if its path is lost, so they
+            // stay on the same branch when they can
+            let mut args = initial.clone();
+            let start = tracked.as_ref().or(last.as_ref().map(|(_, x)| x));
+            if let Some(x) = start {
+                for (&var, v) in block.vars.iter().zip(x) {
+                    args[var] = *v
+                }
+            }
+            args[param] = value;
+
+            let solution = self
+                .solve_block(&mut args, &block, iter)
+                .then(|| block.vars.iter().map(|&v| args[v]).collect::<Vec<_>>());
+            if let Some(x) = &solution {
+                last = Some((value, x.clone()));
+            }
+            out.push(solution)
+        }
+        out
+    }
+
+    /// Follows the solution from `initial`, the trivial solution of `x - initial = 0`, to a
+    /// solution of the system, falling back to plain Newton iterations if the path is lost
+    pub fn solve_homotopy<R: Iterator<Item = Complex64>>(
+        &self,
+        iter: usize,
+        initial: R,
+    ) -> Option<Vec<Complex64>> {
+        self.converge_homotopy(iter, initial, &mut NewtonStats::default())
+    }
+
+    /// `solve_homotopy`, adding what the Newton iterations did to `stats`
+    pub fn converge_homotopy<R: Iterator<Item = Complex64>>(
+        &self,
+        iter: usize,
+        initial: R,
+        stats: &mut NewtonStats,
+    ) -> Option<Vec<Complex64>> {
+        let start: Vec<Complex64> = initial.take(self.var_amount).collect();
+        if self.eqs.len() != self.var_amount || start.len() != self.var_amount {
+            return self.converge(iter, start.into_iter(), stats);
+        }
+
+        let h = StartHomotopy {
+            system: self,
+            start: DVector::from_vec(start.clone()),
+            gamma: Complex64::from_polar(1.0, GAMMA_ANGLE),
+        };
+        let Some(mut solution) = track(&h, start.clone()) else {
+            return self.converge(iter, start.into_iter(), stats);
+        };
+        self.converge_block(&mut solution, &self.whole_block(), iter, stats)
+            .then_some(solution)
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::{BTreeMap, HashMap};
+
+use num_complex::Complex64;
+
+use crate::{
+    error::{Error, Side},
+    parsing::{
+        ast::{ExprNode, Function},
+        operators::{BinOp, UnaryOp},
+        units::Dimension,
+    },
+};
+
+use super::{running::Branch, system::System};
+
+/// Coefficients below this count as zero during elimination
+const COEF_TOLERANCE: f64 = 1e-9;
+
+/// A dimension as a linear combination of the unknown dimensions of some variables plus a
+/// constant, in which the variables are the keys of `vars`
+#[derive(Debug, Clone, Default)]
+struct LinearDim {
+    vars: HashMap<usize, f64>,
+    constant: Dimension,
+}
+
+impl LinearDim {
+    fn constant(dim: Dimension) -> Self {
+        LinearDim {
+            vars: HashMap::new(),
+            constant: dim,
+        }
+    }
+
+    /// `self + other * factor`
+    fn add(mut self, other: &LinearDim, factor: f64) -> LinearDim {
+        for (var, c) in &other.vars {
+            *self.vars.entry(*var).or_insert(0.0) += c * factor;
+        }
+        self.vars.retain(|_, c| c.abs() > COEF_TOLERANCE);
+        self.constant = self.constant.add(other.constant, factor);
+        self
+    }
+
+    fn scale(self, factor: f64) -> LinearDim {
+        LinearDim::default().add(&self, factor)
+    }
+}
+
+/// A dimension that has to be zero, and where it comes from
+struct Constraint {
+    dim: LinearDim,
+    eq: usize,
+    side: Side,
+    msg: String,
+}
+
+/// Collects the constraints on the dimensions of variables, given the ones already known
+struct Checker<'a> {
+    known: &'a [Option<Dimension>],
+    constraints: Vec<Constraint>,
+    eq: usize,
+    side: Side,
+}
+
+impl Checker<'_> {
+    fn require(&mut self, dim: LinearDim, msg: impl Into<String>) {
+        self.constraints.push(Constraint {
+            dim,
+            eq: self.eq,
+            side: self.side,
+            msg: msg.into(),
+        })
+    }
+
+    fn dim(&mut self, node: &ExprNode) -> LinearDim {
+        match node {
+            ExprNode::Number(_)
+            | ExprNode::Decimal(..)
+            | ExprNode::E
+            | ExprNode::Pi
+            | ExprNode::I => LinearDim::default(),
+            ExprNode::Unit(unit) => LinearDim::constant(unit.dim),
+            ExprNode::Var(id) => match self.known.get(*id as usize).copied().flatten() {
+                Some(dim) => LinearDim::constant(dim),
+                None => LinearDim {
+                    vars: HashMap::from([(*id as usize, 1.0)]),
+                    constant: Dimension::NONE,
+                },
+            },
+            ExprNode::BinOp(a, op, b) => {
+                let da = self.dim(a);
+                let db = self.dim(b);
+                match op {
+                    BinOp::Plus | BinOp::Minus | BinOp::Mod => {
+                        self.require(
+                            da.clone().add(&db, -1.0),
+                            format!(
+                                "Cannot use {} on quantities with different units",
+                                op.name()
+                            ),
+                        );
+                        da
+                    }
+                    BinOp::Mult => da.add(&db, 1.0),
+                    BinOp::Div => da.add(&db, -1.0),
+                    BinOp::Pow => {
+                        self.require(db, "Exponents cannot have units");
+                        let exp = b
+                            .is_constant()
+                            .then(|| b.run::<Complex64>(&[], Branch::default()));
+                        match exp {
+                            Some(exp) if exp.im == 0.0 && exp.re.is_finite() => da.scale(exp.re),
+                            _ => {
+                                self.require(
+                                    da,
+                                    "A quantity with units can only be raised to a constant power",
+                                );
+                                LinearDim::default()
+                            }
+                        }
+                    }
+                }
+            }
+            ExprNode::UnaryOp(UnaryOp::Plus | UnaryOp::Minus, v) | ExprNode::Abs(v) => self.dim(v),
+            ExprNode::Func(f, v) => {
+                let dv = self.dim(v);
+                match f {
+                    Function::Sqrt => dv.scale(0.5),
+                    Function::Cbrt => dv.scale(1.0 / 3.0),
+                    Function::Arg => LinearDim::default(),
+                    f => {
+                        self.require(
+                            dv,
+                            format!("The argument of `{}` cannot have units", f.name()),
+                        );
+                        LinearDim::default()
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Units of the variables that satisfy every constraint, given the `known` ones, or the error
+/// of the first constraint that contradicts the ones before it
+fn solve(
+    known: Vec<Option<Dimension>>,
+    constraints: Vec<Constraint>,
+) -> Result<Vec<Option<Dimension>>, Error> {
+    // Gaussian elimination, adding the constraints one at a time so the first one that
+    // contradicts the others is the one reported. Each row solves for its pivot variable.
+    let mut rows: Vec<(usize, LinearDim)> = vec![];
+    let mut pivot_row: HashMap<usize, usize> = HashMap::new();
+    for constraint in constraints {
+        let mut dim = constraint.dim;
+        // eliminating a pivot only brings in pivots of later rows, so go in row order
+        let mut pending: BTreeMap<usize, usize> = dim
+            .vars
+            .keys()
+            .filter_map(|v| Some((*pivot_row.get(v)?, *v)))
+            .collect();
+        while let Some((row, var)) = pending.pop_first() {
+            let Some(c) = dim.vars.get(&var).copied() else {
+                continue;
+            };
+            let (_, pivot) = &rows[row];
+            dim = dim.add(pivot, -c);
+            pending.extend(
+                pivot
+                    .vars
+                    .keys()
+                    .filter_map(|v| Some((*pivot_row.get(v)?, *v)))
+                    .filter(|(r, _)| *r > row),
+            );
+        }
+
+        let Some((&var, &c)) = dim
+            .vars
+            .iter()
+            .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+        else {
+            if dim.constant.is_none() {
+                continue;
+            }
+            return Err(Error::Units {
+                eq: constraint.eq,
+                side: constraint.side,
+                msg: constraint.msg,
+            });
+        };
+        pivot_row.insert(var, rows.len());
+        rows.push((var, dim.scale(1.0 / c)));
+    }
+
+    // later rows never contain earlier pivots, so solve from the last row up
+    let mut dims = known;
+    for (var, row) in rows.iter().rev() {
+        let others = row.vars.iter().filter(|(v, _)| *v != var);
+        if others.clone().any(|(v, _)| dims[*v].is_none()) {
+            continue;
+        }
+        let dim = others.fold(row.constant.scale(-1.0), |dim, (v, c)| {
+            dim.add(dims[*v].unwrap_or_default(), -c)
+        });
+        dims[*var] = Some(dim);
+    }
+    Ok(dims)
+}
+
+impl ExprNode {
+    /// Checks that the units within the expression agree, whatever the units of its
+    /// `var_amount` variables are
+    pub fn check_units(&self, var_amount: usize) -> Result<(), Error> {
+        let known = vec![None; var_amount];
+        let mut checker = Checker {
+            known: &known,
+            constraints: vec![],
+            eq: 0,
+            side: Side::Left,
+        };
+        checker.dim(self);
+        let constraints = checker.constraints;
+        solve(known, constraints).map(|_| ())
+    }
+}
+
+impl System {
+    /// Checks that the units of every equation agree, and infers the units of the variables
+    /// without one from them. Variables whose units the equations do not fix are left `None`.
+    pub fn check_units(&mut self) -> Result<(), Error> {
+        let known: Vec<Option<Dimension>> = self.symbols.iter().map(|s| s.unit).collect();
+        let mut checker = Checker {
+            known: &known,
+            constraints: vec![],
+            eq: 0,
+            side: Side::Left,
+        };
+        for (i, eq) in self.eqs.iter().enumerate() {
+            checker.eq = i;
+            checker.side = Side::Left;
+            let left = checker.dim(&eq.left);
+            checker.side = Side::Right;
+            let right = checker.dim(&eq.right);
+            checker.require(
+                left.add(&right, -1.0),
+                "Both sides of the equation must have the same units",
+            );
+        }
+        let constraints = checker.constraints;
+
+        let dims = solve(known, constraints)?;
+        for (id, dim) in dims.into_iter().enumerate() {
+            if let Some(symbol) = self.symbols.get_mut(id as u32) {
+                symbol.unit = dim;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,719 @@
+use std::{
+    f64::consts,
+    fmt::{self, Display},
+    num::ParseFloatError,
+    ops::{Add, Div, Mul, Neg, Rem, Sub},
+    str::FromStr,
+};
+
+use num_complex::Complex64;
+
+/// Terms of series smaller than this, relative to their sum, are left out
+const SERIES_TOLERANCE: f64 = 1e-34;
+/// Parts of complex numbers smaller than this, relative to the other part, are not shown
+const PART_TOLERANCE: f64 = 1e-30;
+/// Significant digits shown by default, about what a `DoubleDouble` holds
+pub const DEFAULT_DIGITS: usize = 32;
+/// Largest argument of `sin` and `cos` reduced in double-double, beyond which the bits of `π/2`
+/// that are kept no longer give the remainder to `f64` precision
+const MAX_REDUCED: f64 = 1e30;
+/// `π/2` to about 160 bits, as the sum of three `f64`
+const FRAC_PI_2_PARTS: [f64; 3] = [
+    consts::FRAC_PI_2,
+    6.123233995736766e-17,
+    -1.4973849048591698e-33,
+];
+
+/// A real number as the unevaluated sum `hi + lo` of two `f64` with `|lo| <= ulp(hi) / 2`,
+/// which holds about 32 significant digits
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DoubleDouble {
+    pub hi: f64,
+    pub lo: f64,
+}
+
+/// `a + b` exactly, as the rounded sum and its error
+fn two_sum(a: f64, b: f64) -> DoubleDouble {
+    let s = a + b;
+    let bb = s - a;
+    let err = (a - (s - bb)) + (b - bb);
+    DoubleDouble::renorm(s, err)
+}
+
+/// `a * b` exactly, as the rounded product and its error
+fn two_prod(a: f64, b: f64) -> DoubleDouble {
+    let p = a * b;
+    DoubleDouble::renorm(p, a.mul_add(b, -p))
+}
+
+impl DoubleDouble {
+    pub const ZERO: DoubleDouble = DoubleDouble { hi: 0.0, lo: 0.0 };
+    pub const ONE: DoubleDouble = DoubleDouble { hi: 1.0, lo: 0.0 };
+    pub const PI: DoubleDouble = DoubleDouble {
+        hi: consts::PI,
+        lo: 1.2246467991473532e-16,
+    };
+    pub const E: DoubleDouble = DoubleDouble {
+        hi: consts::E,
+        lo: 1.4456468917292502e-16,
+    };
+    const LN_2: DoubleDouble = DoubleDouble {
+        hi: consts::LN_2,
+        lo: 2.3190468138462996e-17,
+    };
+    const FRAC_PI_2: DoubleDouble = DoubleDouble {
+        hi: consts::FRAC_PI_2,
+        lo: 6.123233995736766e-17,
+    };
+
+    /// `hi + lo` for `|lo|` not much larger than `ulp(hi)`
+    fn renorm(hi: f64, lo: f64) -> Self {
+        if !hi.is_finite() {
+            // the error of a sum or product that overflowed is NaN
+            return DoubleDouble { hi, lo: 0.0 };
+        }
+        let s = hi + lo;
+        if !s.is_finite() {
+            return DoubleDouble { hi: s, lo: 0.0 };
+        }
+        DoubleDouble {
+            hi: s,
+            lo: lo - (s - hi),
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    pub fn is_finite(self) -> bool {
+        self.hi.is_finite()
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.hi == 0.0
+    }
+
+    pub fn abs(self) -> Self {
+        if self.hi.is_sign_negative() {
+            -self
+        } else {
+            self
+        }
+    }
+
+    fn square(self) -> Self {
+        self * self
+    }
+
+    /// `self * 2^exp`, which is exact
+    fn mul_pow2(self, exp: i32) -> Self {
+        // split so the factor stays a normal number
+        let half = exp / 2;
+        let (a, b) = (2f64.powi(half), 2f64.powi(exp - half));
+        DoubleDouble {
+            hi: self.hi * a * b,
+            lo: self.lo * a * b,
+        }
+    }
+
+    pub fn floor(self) -> Self {
+        let hi = self.hi.floor();
+        if hi == self.hi {
+            DoubleDouble::renorm(hi, self.lo.floor())
+        } else {
+            DoubleDouble::from(hi)
+        }
+    }
+
+    pub fn trunc(self) -> Self {
+        if self.hi < 0.0 {
+            -(-self).floor()
+        } else {
+            self.floor()
+        }
+    }
+
+    pub fn sqrt(self) -> Self {
+        if self.hi <= 0.0 || !self.is_finite() {
+            return DoubleDouble::from(self.hi.sqrt());
+        }
+        // one Newton step from the `f64` root doubles the digits
+        let x = 1.0 / self.hi.sqrt();
+        let ax = DoubleDouble::from(self.hi * x);
+        ax + DoubleDouble::from((self - ax.square()).hi * (x * 0.5))
+    }
+
+    pub fn cbrt(self) -> Self {
+        if self.is_zero() || !self.is_finite() {
+            return DoubleDouble::from(self.hi.cbrt());
+        }
+        let x = DoubleDouble::from(self.hi.cbrt());
+        x - (x.square() * x - self) / (x.square() * 3.0.into())
+    }
+
+    pub fn exp(self) -> Self {
+        if self.hi > 709.8 {
+            return f64::INFINITY.into();
+        }
+        if self.hi < -745.2 {
+            return DoubleDouble::ZERO;
+        }
+        if self.hi.is_nan() {
+            return self;
+        }
+        // e^x = 2^k e^r, and e^r is found from the series of e^(r/1024) - 1 by squaring
+        let k = (self.hi / DoubleDouble::LN_2.hi).round();
+        let r = (self - DoubleDouble::LN_2 * k.into()).mul_pow2(-10);
+        let mut term = r;
+        let mut sum = r;
+        let mut n = 1.0;
+        while term.hi.abs() > SERIES_TOLERANCE * sum.hi.abs() {
+            n += 1.0;
+            term = term * r / n.into();
+            sum = sum + term;
+        }
+        for _ in 0..10 {
+            // (1 + s)^2 - 1, keeping the small value so no digits are lost
+            sum = sum.mul_pow2(1) + sum.square();
+        }
+        (sum + DoubleDouble::ONE).mul_pow2(k as i32)
+    }
+
+    pub fn ln(self) -> Self {
+        if self.hi <= 0.0 || !self.is_finite() {
+            return DoubleDouble::from(self.hi.ln());
+        }
+        // Newton steps on `e^x = self`, each doubling the digits of the `f64` logarithm
+        let mut x = DoubleDouble::from(self.hi.ln());
+        for _ in 0..2 {
+            x = x + self * (-x).exp() - DoubleDouble::ONE;
+        }
+        x
+    }
+
+    /// `(sin(self), cos(self))`
+    pub fn sin_cos(self) -> (Self, Self) {
+        if !self.is_finite() {
+            return (f64::NAN.into(), f64::NAN.into());
+        }
+        if self.hi.abs() > MAX_REDUCED {
+            return (self.hi.sin().into(), self.hi.cos().into());
+        }
+        // reduce to |t| <= π/4 and sum both series. `j` is an integer with up to about 100
+        // bits, and each of its parts times each part of `π/2` is exact.
+        let j = (self / DoubleDouble::FRAC_PI_2 + 0.5.into()).floor();
+        let t = FRAC_PI_2_PARTS
+            .iter()
+            .flat_map(|&p| [two_prod(j.hi, p), two_prod(j.lo, p)])
+            .fold(self, |t, jp| t - jp);
+        let t2 = t.square();
+        let (mut sin, mut sin_term) = (t, t);
+        let (mut cos, mut cos_term) = (DoubleDouble::ONE, DoubleDouble::ONE);
+        let mut n = 0.0;
+        while sin_term.hi.abs() > SERIES_TOLERANCE * sin.hi.abs()
+            || cos_term.hi.abs() > SERIES_TOLERANCE
+        {
+            cos_term = -cos_term * t2 / ((n + 1.0) * (n + 2.0)).into();
+            sin_term = -sin_term * t2 / ((n + 2.0) * (n + 3.0)).into();
+            cos = cos + cos_term;
+            sin = sin + sin_term;
+            n += 2.0;
+        }
+        match ((j.hi % 4.0 + j.lo % 4.0) as i64).rem_euclid(4) {
+            0 => (sin, cos),
+            1 => (cos, -sin),
+            2 => (-sin, -cos),
+            _ => (-cos, sin),
+        }
+    }
+
+    /// `(sinh(self), cosh(self))`
+    pub fn sinh_cosh(self) -> (Self, Self) {
+        if self.hi.abs() >= 0.5 {
+            let e = self.exp();
+            let inv = DoubleDouble::ONE / e;
+            return ((e - inv).mul_pow2(-1), (e + inv).mul_pow2(-1));
+        }
+        // the series avoids the cancellation in `e^x - e^-x`
+        let x2 = self.square();
+        let (mut sinh, mut term) = (self, self);
+        let mut n = 1.0;
+        while term.hi.abs() > SERIES_TOLERANCE * sinh.hi.abs() {
+            term = term * x2 / ((n + 1.0) * (n + 2.0)).into();
+            sinh = sinh + term;
+            n += 2.0;
+        }
+        (sinh, (DoubleDouble::ONE + sinh.square()).sqrt())
+    }
+
+    /// `hypot(self, other)`, scaled by a power of two so the squares cannot overflow
+    pub fn hypot(self, other: Self) -> Self {
+        let max = self.hi.abs().max(other.hi.abs());
+        if max == 0.0 || !max.is_finite() {
+            return DoubleDouble::from(self.hi.hypot(other.hi));
+        }
+        let exp = max.log2().floor() as i32;
+        let (a, b) = (self.mul_pow2(-exp), other.mul_pow2(-exp));
+        (a.square() + b.square()).sqrt().mul_pow2(exp)
+    }
+
+    /// The angle of the point `(x, y)`, like `f64::atan2`
+    pub fn atan2(y: Self, x: Self) -> Self {
+        let z = DoubleDouble::from(y.hi.atan2(x.hi));
+        if (y.is_zero() && x.hi >= 0.0) || !x.is_finite() || !y.is_finite() {
+            return z;
+        }
+        // one Newton step on whichever of `sin` and `cos` is steeper there
+        let r = y.hypot(x);
+        let (x, y) = (x / r, y / r);
+        let (sin, cos) = z.sin_cos();
+        if x.hi.abs() > y.hi.abs() {
+            z + (y - sin) / cos
+        } else {
+            z - (x - cos) / sin
+        }
+    }
+
+    /// The digits of `|self|` rounded to `digits` significant ones, and the exponent of the
+    /// first
+    fn decimal_digits(self, digits: usize) -> (Vec<u8>, i32) {
+        let mut x = self.abs();
+        let mut exp = x.hi.log10().floor() as i32;
+        x = x / DoubleDouble::from(10.0).powi(exp);
+        // the `f64` logarithm can be off by one
+        if x.hi >= 10.0 {
+            x = x / 10.0.into();
+            exp += 1;
+        } else if x.hi < 1.0 {
+            x = x * 10.0.into();
+            exp -= 1;
+        }
+
+        let mut out = Vec::with_capacity(digits + 1);
+        for _ in 0..=digits {
+            let d = x.floor().hi.clamp(0.0, 9.0);
+            out.push(d as u8);
+            x = (x - d.into()) * 10.0.into();
+        }
+        // round, carrying into the previous digits
+        if out.pop().unwrap_or(0) >= 5 {
+            let mut i = out.len();
+            loop {
+                if i == 0 {
+                    out.insert(0, 1);
+                    out.pop();
+                    exp += 1;
+                    break;
+                }
+                i -= 1;
+                if out[i] == 9 {
+                    out[i] = 0;
+                } else {
+                    out[i] += 1;
+                    break;
+                }
+            }
+        }
+        (out, exp)
+    }
+
+    fn powi(self, exp: i32) -> Self {
+        let mut out = DoubleDouble::ONE;
+        let mut base = if exp < 0 {
+            DoubleDouble::ONE / self
+        } else {
+            self
+        };
+        let mut exp = exp.unsigned_abs();
+        while exp > 0 {
+            if exp % 2 == 1 {
+                out = out * base;
+            }
+            base = base.square();
+            exp /= 2;
+        }
+        out
+    }
+}
+
+/// Reads a decimal like `12.345` digit by digit, to within a few units in the last place
+/// rather than rounding it to an `f64` first. Other forms that `f64` reads are read as `f64`.
+impl FromStr for DoubleDouble {
+    type Err = ParseFloatError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let approx: f64 = s.parse()?;
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        if !approx.is_finite() || !(int.bytes().chain(frac.bytes())).all(|b| b.is_ascii_digit()) {
+            return Ok(approx.into());
+        }
+        let ten = DoubleDouble::from(10.0);
+        let mantissa = int
+            .bytes()
+            .chain(frac.bytes())
+            .fold(DoubleDouble::ZERO, |v, b| {
+                v * ten + f64::from(b - b'0').into()
+            });
+        let out = mantissa / ten.powi(frac.len() as i32);
+        Ok(if out.is_finite() { out } else { approx.into() })
+    }
+}
+
+impl From<f64> for DoubleDouble {
+    fn from(v: f64) -> Self {
+        DoubleDouble { hi: v, lo: 0.0 }
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = Self;
+    fn neg(self) -> Self {
+        DoubleDouble {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        let s = two_sum(self.hi, other.hi);
+        let t = two_sum(self.lo, other.lo);
+        let s = DoubleDouble::renorm(s.hi, s.lo + t.hi);
+        DoubleDouble::renorm(s.hi, s.lo + t.lo)
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        let p = two_prod(self.hi, other.hi);
+        DoubleDouble::renorm(p.hi, p.lo + (self.hi * other.lo + self.lo * other.hi))
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        // long division, one `f64` quotient digit at a time
+        let q1 = self.hi / other.hi;
+        if !q1.is_finite() {
+            return q1.into();
+        }
+        let r = self - other * q1.into();
+        let q2 = r.hi / other.hi;
+        let r = r - other * q2.into();
+        let q3 = r.hi / other.hi;
+        DoubleDouble::renorm(q1, q2) + q3.into()
+    }
+}
+
+impl Display for DoubleDouble {
+    /// Writes the number to 32 significant digits, or as many as the precision asks for, in
+    /// scientific notation when it is very large or small
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.is_finite() || self.is_zero() {
+            return write!(f, "{}", self.hi);
+        }
+        if self.hi < 0.0 {
+            f.write_str("-")?;
+        }
+        let digits = f.precision().unwrap_or(DEFAULT_DIGITS).max(1);
+        let (mut digits, exp) = self.decimal_digits(digits);
+        while digits.len() > 1 && digits.last() == Some(&0) {
+            digits.pop();
+        }
+        let digits: String = digits.iter().map(|d| char::from(b'0' + d)).collect();
+
+        if !(-5..21).contains(&exp) {
+            let (first, rest) = digits.split_at(1);
+            if rest.is_empty() {
+                return write!(f, "{}e{}", first, exp);
+            }
+            return write!(f, "{}.{}e{}", first, rest, exp);
+        }
+        if exp < 0 {
+            return write!(f, "0.{}{}", "0".repeat((-exp - 1) as usize), digits);
+        }
+        let int_len = exp as usize + 1;
+        if digits.len() <= int_len {
+            write!(f, "{}{}", digits, "0".repeat(int_len - digits.len()))
+        } else {
+            write!(f, "{}.{}", &digits[..int_len], &digits[int_len..])
+        }
+    }
+}
+
+/// A complex number with `DoubleDouble` parts
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ComplexDD {
+    pub re: DoubleDouble,
+    pub im: DoubleDouble,
+}
+
+impl ComplexDD {
+    pub fn new(re: DoubleDouble, im: DoubleDouble) -> Self {
+        ComplexDD { re, im }
+    }
+
+    pub fn i() -> Self {
+        ComplexDD::new(DoubleDouble::ZERO, DoubleDouble::ONE)
+    }
+
+    fn real(v: DoubleDouble) -> Self {
+        ComplexDD::new(v, DoubleDouble::ZERO)
+    }
+
+    /// The nearest `Complex64`
+    pub fn to_complex(self) -> Complex64 {
+        Complex64::new(self.re.to_f64(), self.im.to_f64())
+    }
+
+    fn is_zero(self) -> bool {
+        self.re.is_zero() && self.im.is_zero()
+    }
+
+    fn from_polar(r: DoubleDouble, theta: DoubleDouble) -> Self {
+        let (sin, cos) = theta.sin_cos();
+        ComplexDD::new(r * cos, r * sin)
+    }
+
+    pub fn norm(self) -> DoubleDouble {
+        self.re.hypot(self.im)
+    }
+
+    pub fn arg(self) -> DoubleDouble {
+        DoubleDouble::atan2(self.im, self.re)
+    }
+
+    // the functions below follow the formulas and branch cuts of `num_complex`
+
+    pub fn exp(self) -> Self {
+        if self.re.hi == f64::NEG_INFINITY && !self.im.is_finite() {
+            return ComplexDD::default();
+        }
+        if self.im.is_zero() {
+            // `e^x * 0` would be NaN where `e^x` overflows
+            return ComplexDD::new(self.re.exp(), self.im);
+        }
+        ComplexDD::from_polar(self.re.exp(), self.im)
+    }
+
+    pub fn ln(self) -> Self {
+        ComplexDD::new(self.norm().ln(), self.arg())
+    }
+
+    pub fn sqrt(self) -> Self {
+        if self.im.is_zero() {
+            if self.re.hi.is_sign_positive() {
+                return ComplexDD::new(self.re.sqrt(), self.im);
+            }
+            let root = (-self.re).sqrt();
+            return if self.im.hi.is_sign_positive() {
+                ComplexDD::new(DoubleDouble::ZERO, root)
+            } else {
+                ComplexDD::new(DoubleDouble::ZERO, -root)
+            };
+        }
+        // the half-angle formulas, picking the one without cancellation
+        let t = ((self.norm() + self.re.abs()).mul_pow2(-1)).sqrt();
+        if self.re.hi >= 0.0 {
+            ComplexDD::new(t, self.im / t.mul_pow2(1))
+        } else if self.im.hi > 0.0 {
+            ComplexDD::new(self.im.abs() / t.mul_pow2(1), t)
+        } else {
+            ComplexDD::new(self.im.abs() / t.mul_pow2(1), -t)
+        }
+    }
+
+    pub fn cbrt(self) -> Self {
+        if self.im.is_zero() && self.re.hi.is_sign_positive() {
+            return ComplexDD::new(self.re.cbrt(), self.im);
+        }
+        ComplexDD::from_polar(self.norm().cbrt(), self.arg() / DoubleDouble::from(3.0))
+    }
+
+    pub fn powc(self, exp: Self) -> Self {
+        if exp.is_zero() {
+            return ComplexDD::real(DoubleDouble::ONE);
+        }
+        // small integer powers by multiplying, which loses fewer digits than the logarithm
+        let n = exp.re.hi;
+        if exp.im.is_zero() && exp.re.lo == 0.0 && n.fract() == 0.0 && n.abs() <= 64.0 {
+            let mut out = ComplexDD::real(DoubleDouble::ONE);
+            for _ in 0..n.abs() as u32 {
+                out = out * self;
+            }
+            return if n < 0.0 {
+                ComplexDD::real(DoubleDouble::ONE) / out
+            } else {
+                out
+            };
+        }
+        (exp * self.ln()).exp()
+    }
+
+    pub fn sin(self) -> Self {
+        let (sin, cos) = self.re.sin_cos();
+        let (sinh, cosh) = self.im.sinh_cosh();
+        ComplexDD::new(sin * cosh, cos * sinh)
+    }
+
+    pub fn cos(self) -> Self {
+        let (sin, cos) = self.re.sin_cos();
+        let (sinh, cosh) = self.im.sinh_cosh();
+        ComplexDD::new(cos * cosh, -(sin * sinh))
+    }
+
+    pub fn tan(self) -> Self {
+        let (sin, cos) = self.re.mul_pow2(1).sin_cos();
+        let (sinh, cosh) = self.im.mul_pow2(1).sinh_cosh();
+        let den = cos + cosh;
+        ComplexDD::new(sin / den, sinh / den)
+    }
+
+    pub fn sinh(self) -> Self {
+        let (sin, cos) = self.im.sin_cos();
+        let (sinh, cosh) = self.re.sinh_cosh();
+        ComplexDD::new(sinh * cos, cosh * sin)
+    }
+
+    pub fn cosh(self) -> Self {
+        let (sin, cos) = self.im.sin_cos();
+        let (sinh, cosh) = self.re.sinh_cosh();
+        ComplexDD::new(cosh * cos, sinh * sin)
+    }
+
+    pub fn tanh(self) -> Self {
+        let (sin, cos) = self.im.mul_pow2(1).sin_cos();
+        let (sinh, cosh) = self.re.mul_pow2(1).sinh_cosh();
+        let den = cosh + cos;
+        ComplexDD::new(sinh / den, sin / den)
+    }
+
+    pub fn asin(self) -> Self {
+        let one = ComplexDD::real(DoubleDouble::ONE);
+        -ComplexDD::i() * ((one - self * self).sqrt() + ComplexDD::i() * self).ln()
+    }
+
+    pub fn atan(self) -> Self {
+        let i = ComplexDD::i();
+        let one = ComplexDD::real(DoubleDouble::ONE);
+        if self == i || self == -i {
+            let inf = DoubleDouble::from(f64::INFINITY);
+            return ComplexDD::new(DoubleDouble::ZERO, if self == i { inf } else { -inf });
+        }
+        ((one + i * self).ln() - (one - i * self).ln()) / (i + i)
+    }
+
+    pub fn asinh(self) -> Self {
+        let one = ComplexDD::real(DoubleDouble::ONE);
+        (self + (one + self * self).sqrt()).ln()
+    }
+
+    pub fn acosh(self) -> Self {
+        let one = ComplexDD::real(DoubleDouble::ONE);
+        let two = one + one;
+        two * (((self + one) / two).sqrt() + ((self - one) / two).sqrt()).ln()
+    }
+
+    pub fn atanh(self) -> Self {
+        let one = ComplexDD::real(DoubleDouble::ONE);
+        if self == one || self == -one {
+            let inf = DoubleDouble::from(f64::INFINITY);
+            return ComplexDD::real(if self == one { inf } else { -inf });
+        }
+        ((one + self).ln() - (one - self).ln()) / (one + one)
+    }
+}
+
+impl From<Complex64> for ComplexDD {
+    fn from(v: Complex64) -> Self {
+        ComplexDD::new(v.re.into(), v.im.into())
+    }
+}
+
+impl Neg for ComplexDD {
+    type Output = Self;
+    fn neg(self) -> Self {
+        ComplexDD::new(-self.re, -self.im)
+    }
+}
+
+impl Add for ComplexDD {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        ComplexDD::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for ComplexDD {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        ComplexDD::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for ComplexDD {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        ComplexDD::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Div for ComplexDD {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        let den = other.re * other.re + other.im * other.im;
+        ComplexDD::new(
+            (self.re * other.re + self.im * other.im) / den,
+            (self.im * other.re - self.re * other.im) / den,
+        )
+    }
+}
+
+impl Rem for ComplexDD {
+    type Output = Self;
+    fn rem(self, modulus: Self) -> Self {
+        let q = self / modulus;
+        self - modulus * ComplexDD::new(q.re.trunc(), q.im.trunc())
+    }
+}
+
+impl Display for ComplexDD {
+    /// Writes the number like `1.5 - 2i`, leaving out a part that is below the precision of the
+    /// other
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = f.precision().unwrap_or(DEFAULT_DIGITS);
+        let negligible = |part: DoubleDouble, other: DoubleDouble| {
+            part.hi.abs() <= PART_TOLERANCE * other.hi.abs()
+        };
+        if negligible(self.im, self.re) {
+            return write!(f, "{:.*}", digits, self.re);
+        }
+        if negligible(self.re, self.im) {
+            return write!(f, "{:.*}i", digits, self.im);
+        }
+        let sign = if self.im.hi < 0.0 { '-' } else { '+' };
+        write!(
+            f,
+            "{:.*} {} {:.*}i",
+            digits,
+            self.re,
+            sign,
+            digits,
+            self.im.abs()
+        )
+    }
+}
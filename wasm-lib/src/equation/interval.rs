@@ -0,0 +1,764 @@
+use std::{
+    f64::consts::{E, PI, TAU},
+    ops::{Add, Div, Mul, Neg, Rem, Sub},
+};
+
+use nalgebra::DMatrix;
+use num_complex::Complex64;
+
+use super::{scalar::Scalar, structure::Block, system::System};
+
+/// Ulps the bounds of the math library functions are widened by, beyond the one of the
+/// correctly rounded arithmetic
+const LIBM_ULPS: usize = 2;
+/// Slack when checking whether an interval reaches an extremum of `sin` or `cos`
+const PERIOD_SLACK: f64 = 1e-12;
+/// Sizes of the boxes tried around a solution, relative to its size
+const CERTIFY_RADII: [f64; 4] = [1e-13, 1e-11, 1e-9, 1e-7];
+
+/// A closed interval of reals. Every operation rounds its bounds outwards, so the result
+/// contains every value the operation can take on the inputs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+fn next_up(v: f64, ulps: usize) -> f64 {
+    (0..ulps).fold(v, |v, _| v.next_up())
+}
+
+fn next_down(v: f64, ulps: usize) -> f64 {
+    (0..ulps).fold(v, |v, _| v.next_down())
+}
+
+impl Interval {
+    pub const ENTIRE: Interval = Interval {
+        lo: f64::NEG_INFINITY,
+        hi: f64::INFINITY,
+    };
+    const ZERO: Interval = Interval { lo: 0.0, hi: 0.0 };
+
+    pub fn new(lo: f64, hi: f64) -> Self {
+        if lo.is_nan() || hi.is_nan() {
+            return Interval::ENTIRE;
+        }
+        Interval { lo, hi }
+    }
+
+    pub fn point(v: f64) -> Self {
+        Interval::new(v, v)
+    }
+
+    /// The interval of rounded bounds, widened by `ulps` on each side
+    fn widened(lo: f64, hi: f64, ulps: usize) -> Self {
+        Interval::new(next_down(lo, ulps), next_up(hi, ulps))
+    }
+
+    /// The smallest interval holding every value of `values`, rounded outwards
+    fn hull(values: impl IntoIterator<Item = f64>, ulps: usize) -> Self {
+        let mut lo = f64::INFINITY;
+        let mut hi = f64::NEG_INFINITY;
+        for v in values {
+            if v.is_nan() {
+                return Interval::ENTIRE;
+            }
+            lo = lo.min(v);
+            hi = hi.max(v);
+        }
+        Interval::widened(lo, hi, ulps)
+    }
+
+    pub fn mid(self) -> f64 {
+        self.lo / 2.0 + self.hi / 2.0
+    }
+
+    /// The midpoint, or NaN if the interval holds zero and other values, so comparing it
+    /// against zero holds over the whole interval
+    fn signed_mid(self) -> f64 {
+        if self.contains(0.0) && !self.is_point() {
+            f64::NAN
+        } else {
+            self.mid()
+        }
+    }
+
+    /// Largest absolute value in the interval
+    pub fn mag(self) -> f64 {
+        self.lo.abs().max(self.hi.abs())
+    }
+
+    pub fn contains(self, v: f64) -> bool {
+        self.lo <= v && v <= self.hi
+    }
+
+    fn is_point(self) -> bool {
+        self.lo == self.hi
+    }
+
+    /// Whether `self` lies inside `other` without touching its bounds
+    pub fn is_interior(self, other: Interval) -> bool {
+        other.lo < self.lo && self.hi < other.hi
+    }
+
+    fn sqr(self) -> Self {
+        let (a, b) = (self.lo * self.lo, self.hi * self.hi);
+        if self.contains(0.0) {
+            Interval::widened(0.0, a.max(b), 1)
+        } else {
+            Interval::hull([a, b], 1)
+        }
+    }
+
+    fn sqrt(self) -> Self {
+        if self.hi < 0.0 {
+            return Interval::ENTIRE;
+        }
+        Interval::widened(self.lo.max(0.0).sqrt(), self.hi.sqrt(), 1).clamp_below(0.0)
+    }
+
+    fn clamp_below(self, lo: f64) -> Self {
+        Interval::new(self.lo.max(lo), self.hi)
+    }
+
+    fn exp(self) -> Self {
+        Interval::widened(self.lo.exp(), self.hi.exp(), LIBM_ULPS).clamp_below(0.0)
+    }
+
+    fn ln(self) -> Self {
+        if self.hi < 0.0 {
+            return Interval::ENTIRE;
+        }
+        Interval::widened(self.lo.max(0.0).ln(), self.hi.ln(), LIBM_ULPS)
+    }
+
+    /// Whether the interval may contain `at + 2πk` for some integer `k`
+    fn reaches(self, at: f64) -> bool {
+        let slack = PERIOD_SLACK * (1.0 + self.mag());
+        let k = ((self.lo - slack - at) / TAU).ceil();
+        at + k * TAU <= self.hi + slack
+    }
+
+    /// The range of `sin` or `cos`, given where their maximum and minimum are
+    fn periodic(self, f: fn(f64) -> f64, max_at: f64, min_at: f64) -> Self {
+        let width = self.hi - self.lo;
+        if width.is_nan() || width >= TAU {
+            return Interval::new(-1.0, 1.0);
+        }
+        let mut out = Interval::hull([f(self.lo), f(self.hi)], LIBM_ULPS);
+        if self.reaches(max_at) {
+            out.hi = 1.0
+        }
+        if self.reaches(min_at) {
+            out.lo = -1.0
+        }
+        Interval::new(out.lo.max(-1.0), out.hi.min(1.0))
+    }
+
+    fn sin(self) -> Self {
+        self.periodic(f64::sin, PI / 2.0, -PI / 2.0)
+    }
+
+    fn cos(self) -> Self {
+        self.periodic(f64::cos, 0.0, PI)
+    }
+
+    fn sinh(self) -> Self {
+        Interval::widened(self.lo.sinh(), self.hi.sinh(), LIBM_ULPS)
+    }
+
+    fn cosh(self) -> Self {
+        let out = Interval::hull([self.lo.cosh(), self.hi.cosh()], LIBM_ULPS);
+        if self.contains(0.0) {
+            Interval::new(1.0, out.hi)
+        } else {
+            out.clamp_below(1.0)
+        }
+    }
+
+    fn trunc(self) -> Self {
+        Interval::new(self.lo.trunc(), self.hi.trunc())
+    }
+}
+
+impl Neg for Interval {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Interval::new(-self.hi, -self.lo)
+    }
+}
+
+impl Add for Interval {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        // exact zeros stay exact, so real numbers keep imaginary parts of exactly zero
+        if self == Interval::ZERO {
+            return other;
+        } else if other == Interval::ZERO {
+            return self;
+        }
+        Interval::widened(self.lo + other.lo, self.hi + other.hi, 1)
+    }
+}
+
+impl Sub for Interval {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Mul for Interval {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        if self == Interval::ZERO || other == Interval::ZERO {
+            return Interval::ZERO;
+        }
+        Interval::hull(
+            [
+                self.lo * other.lo,
+                self.lo * other.hi,
+                self.hi * other.lo,
+                self.hi * other.hi,
+            ],
+            1,
+        )
+    }
+}
+
+impl Div for Interval {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        if other.contains(0.0) {
+            return Interval::ENTIRE;
+        } else if self == Interval::ZERO {
+            return Interval::ZERO;
+        }
+        Interval::hull(
+            [
+                self.lo / other.lo,
+                self.lo / other.hi,
+                self.hi / other.lo,
+                self.hi / other.hi,
+            ],
+            1,
+        )
+    }
+}
+
+/// A rectangle of complex numbers, the product of intervals of their real and imaginary parts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComplexInterval {
+    pub re: Interval,
+    pub im: Interval,
+}
+
+impl ComplexInterval {
+    const ENTIRE: ComplexInterval = ComplexInterval {
+        re: Interval::ENTIRE,
+        im: Interval::ENTIRE,
+    };
+
+    pub fn new(re: Interval, im: Interval) -> Self {
+        ComplexInterval { re, im }
+    }
+
+    pub fn point(v: Complex64) -> Self {
+        ComplexInterval::new(Interval::point(v.re), Interval::point(v.im))
+    }
+
+    /// The square of half-width `radius` around `center`
+    pub fn around(center: Complex64, radius: f64) -> Self {
+        let part = |v: f64| Interval::widened(v - radius, v + radius, 1);
+        ComplexInterval::new(part(center.re), part(center.im))
+    }
+
+    pub fn mid(self) -> Complex64 {
+        Complex64::new(self.re.mid(), self.im.mid())
+    }
+
+    fn real(re: Interval) -> Self {
+        ComplexInterval::new(re, Interval::ZERO)
+    }
+
+    fn is_point(self) -> bool {
+        self.re.is_point() && self.im.is_point()
+    }
+
+    /// Largest distance of a part of the rectangle from zero
+    pub fn mag(self) -> f64 {
+        self.re.mag().max(self.im.mag())
+    }
+
+    /// Whether `self` lies inside `other` without touching its sides
+    pub fn is_interior(self, other: ComplexInterval) -> bool {
+        self.re.is_interior(other.re) && self.im.is_interior(other.im)
+    }
+
+    fn norm(self) -> Interval {
+        (self.re.sqr() + self.im.sqr()).sqrt()
+    }
+
+    /// The principal argument, which is all of `[-π, π]` if the rectangle touches the origin
+    /// or crosses the negative real axis
+    fn arg(self) -> Interval {
+        let (x, y) = (self.re, self.im);
+        let pi = Interval::widened(PI, PI, 1);
+        if y == Interval::ZERO && x.lo > 0.0 {
+            return Interval::ZERO;
+        } else if y == Interval::ZERO && x.hi < 0.0 {
+            return pi;
+        }
+        if (x.contains(0.0) && y.contains(0.0)) || (x.hi < 0.0 && y.lo < 0.0 && 0.0 <= y.hi) {
+            return Interval::new(-pi.hi, pi.hi);
+        }
+        // away from the cut the argument is monotonic along every side
+        let corners = [(x.lo, y.lo), (x.lo, y.hi), (x.hi, y.lo), (x.hi, y.hi)];
+        let out = Interval::hull(corners.iter().map(|(x, y)| y.atan2(*x)), LIBM_ULPS);
+        Interval::new(out.lo.max(-pi.hi), out.hi.min(pi.hi))
+    }
+
+    fn from_polar(r: Interval, theta: Interval) -> Self {
+        if theta == Interval::ZERO {
+            return ComplexInterval::real(r);
+        }
+        ComplexInterval::new(r * theta.cos(), r * theta.sin())
+    }
+
+    fn trunc(self) -> Self {
+        ComplexInterval::new(self.re.trunc(), self.im.trunc())
+    }
+
+    fn powi(self, n: i32) -> Self {
+        let mut out = ComplexInterval::real(Interval::point(1.0));
+        for _ in 0..n.unsigned_abs() {
+            out = out * self;
+        }
+        if n < 0 {
+            ComplexInterval::real(Interval::point(1.0)) / out
+        } else {
+            out
+        }
+    }
+
+    /// The exponent if this is a small integer, which is raised to by multiplying
+    fn small_integer(self) -> Option<i32> {
+        let n = self.re.lo;
+        (self.is_point() && self.im.lo == 0.0 && n.fract() == 0.0 && n.abs() <= 64.0)
+            .then_some(n as i32)
+    }
+}
+
+impl Neg for ComplexInterval {
+    type Output = Self;
+    fn neg(self) -> Self {
+        ComplexInterval::new(-self.re, -self.im)
+    }
+}
+
+impl Add for ComplexInterval {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        ComplexInterval::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for ComplexInterval {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        ComplexInterval::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for ComplexInterval {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        ComplexInterval::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Div for ComplexInterval {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        let den = other.re.sqr() + other.im.sqr();
+        ComplexInterval::new(
+            (self.re * other.re + self.im * other.im) / den,
+            (self.im * other.re - self.re * other.im) / den,
+        )
+    }
+}
+
+impl Rem for ComplexInterval {
+    type Output = Self;
+    fn rem(self, modulus: Self) -> Self {
+        self - modulus * (self / modulus).trunc()
+    }
+}
+
+impl Scalar for ComplexInterval {
+    fn from_f64(v: f64) -> Self {
+        ComplexInterval::real(Interval::point(v))
+    }
+    /// The nearest `f64` widened to hold the decimal itself
+    fn from_decimal(digits: &str) -> Self {
+        let v = digits.parse().unwrap_or(f64::NAN);
+        ComplexInterval::real(Interval::widened(v, v, 1))
+    }
+    /// The midpoint, with NaN parts where they could be zero or not, so that every branch
+    /// decision holds over the whole rectangle
+    fn to_complex(self) -> Complex64 {
+        Complex64::new(self.re.signed_mid(), self.im.signed_mid())
+    }
+    fn i() -> Self {
+        ComplexInterval::new(Interval::ZERO, Interval::point(1.0))
+    }
+    fn e() -> Self {
+        ComplexInterval::real(Interval::widened(E, E, 1))
+    }
+    fn pi() -> Self {
+        ComplexInterval::real(Interval::widened(PI, PI, 1))
+    }
+
+    fn abs(self) -> Self {
+        ComplexInterval::real(self.norm())
+    }
+    fn arg(self) -> Self {
+        ComplexInterval::real(ComplexInterval::arg(self))
+    }
+    fn exp(self) -> Self {
+        ComplexInterval::from_polar(self.re.exp(), self.im)
+    }
+    fn ln(self) -> Self {
+        ComplexInterval::new(self.norm().ln(), ComplexInterval::arg(self))
+    }
+    fn sqrt(self) -> Self {
+        if self.im == Interval::ZERO && self.re.lo >= 0.0 {
+            return ComplexInterval::real(self.re.sqrt());
+        }
+        let half = Interval::point(0.5);
+        ComplexInterval::from_polar(self.norm().sqrt(), ComplexInterval::arg(self) * half)
+    }
+    fn sin(self) -> Self {
+        if self.im == Interval::ZERO {
+            return ComplexInterval::real(self.re.sin());
+        }
+        ComplexInterval::new(
+            self.re.sin() * self.im.cosh(),
+            self.re.cos() * self.im.sinh(),
+        )
+    }
+    fn cos(self) -> Self {
+        if self.im == Interval::ZERO {
+            return ComplexInterval::real(self.re.cos());
+        }
+        ComplexInterval::new(
+            self.re.cos() * self.im.cosh(),
+            -(self.re.sin() * self.im.sinh()),
+        )
+    }
+    fn powc(self, exp: Self) -> Self {
+        match exp.small_integer() {
+            Some(0) => ComplexInterval::from_f64(1.0),
+            Some(n) => self.powi(n),
+            // real roots of negative reals are taken apart by `Branch`, which cannot be
+            // told apart from the principal ones if the sign or the exponent varies
+            None if self.im == Interval::ZERO
+                && self.re.lo < 0.0
+                && (self.re.hi >= 0.0 || !exp.is_point()) =>
+            {
+                ComplexInterval::ENTIRE
+            }
+            None => (exp * self.ln()).exp(),
+        }
+    }
+}
+
+/// A rectangle of values and one of derivatives along a direction, to enclose the Jacobian
+/// over a box
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Dual {
+    v: ComplexInterval,
+    d: ComplexInterval,
+}
+
+impl Dual {
+    fn constant(v: ComplexInterval) -> Self {
+        Dual {
+            v,
+            d: ComplexInterval::from_f64(0.0),
+        }
+    }
+
+    fn is_constant(self) -> bool {
+        self.d == ComplexInterval::from_f64(0.0)
+    }
+
+    /// `f(v)` with the derivative `f'(v)`
+    fn chain(self, v: ComplexInterval, derivative: ComplexInterval) -> Self {
+        Dual {
+            v,
+            d: self.d * derivative,
+        }
+    }
+
+    /// `f(v)` of `|v|` or `arg(v)`, which have no complex derivative, but a real one of
+    /// `positive` or `negative` on reals of that sign
+    fn real_only(self, v: ComplexInterval, positive: f64, negative: f64) -> Self {
+        let slope = match self.v.to_complex() {
+            _ if self.is_constant() => return Dual::constant(v),
+            z if z.im == 0.0 && z.re > 0.0 => positive,
+            z if z.im == 0.0 && z.re < 0.0 => negative,
+            _ => {
+                return Dual {
+                    v,
+                    d: ComplexInterval::ENTIRE,
+                }
+            }
+        };
+        self.chain(v, ComplexInterval::from_f64(slope))
+    }
+}
+
+impl Neg for Dual {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Dual {
+            v: -self.v,
+            d: -self.d,
+        }
+    }
+}
+
+impl Add for Dual {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Dual {
+            v: self.v + other.v,
+            d: self.d + other.d,
+        }
+    }
+}
+
+impl Sub for Dual {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Dual {
+            v: self.v - other.v,
+            d: self.d - other.d,
+        }
+    }
+}
+
+impl Mul for Dual {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Dual {
+            v: self.v * other.v,
+            d: self.d * other.v + self.v * other.d,
+        }
+    }
+}
+
+impl Div for Dual {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        let v = self.v / other.v;
+        Dual {
+            v,
+            d: (self.d - v * other.d) / other.v,
+        }
+    }
+}
+
+impl Rem for Dual {
+    type Output = Self;
+    fn rem(self, modulus: Self) -> Self {
+        // `a - m trunc(a/m)`, which is only differentiable where the `trunc` is constant
+        let q = (self.v / modulus.v).trunc();
+        let d = if q.is_point() {
+            self.d - modulus.d * q
+        } else {
+            ComplexInterval::ENTIRE
+        };
+        Dual {
+            v: self.v % modulus.v,
+            d,
+        }
+    }
+}
+
+impl Scalar for Dual {
+    fn from_f64(v: f64) -> Self {
+        Dual::constant(ComplexInterval::from_f64(v))
+    }
+    fn from_decimal(digits: &str) -> Self {
+        Dual::constant(ComplexInterval::from_decimal(digits))
+    }
+    fn to_complex(self) -> Complex64 {
+        self.v.to_complex()
+    }
+    fn i() -> Self {
+        Dual::constant(ComplexInterval::i())
+    }
+    fn e() -> Self {
+        Dual::constant(ComplexInterval::e())
+    }
+    fn pi() -> Self {
+        Dual::constant(ComplexInterval::pi())
+    }
+
+    fn abs(self) -> Self {
+        self.real_only(self.v.abs(), 1.0, -1.0)
+    }
+    fn arg(self) -> Self {
+        self.real_only(Scalar::arg(self.v), 0.0, 0.0)
+    }
+    fn exp(self) -> Self {
+        let v = self.v.exp();
+        self.chain(v, v)
+    }
+    fn ln(self) -> Self {
+        self.chain(self.v.ln(), ComplexInterval::from_f64(1.0) / self.v)
+    }
+    fn sqrt(self) -> Self {
+        let v = self.v.sqrt();
+        self.chain(v, ComplexInterval::from_f64(0.5) / v)
+    }
+    fn sin(self) -> Self {
+        self.chain(self.v.sin(), self.v.cos())
+    }
+    fn cos(self) -> Self {
+        self.chain(self.v.cos(), -self.v.sin())
+    }
+    fn powc(self, exp: Self) -> Self {
+        match exp.v.small_integer() {
+            Some(n) if exp.is_constant() => {
+                let derivative = if n == 0 {
+                    ComplexInterval::from_f64(0.0)
+                } else {
+                    ComplexInterval::from_f64(n as f64) * self.v.powi(n - 1)
+                };
+                self.chain(self.v.powc(exp.v), derivative)
+            }
+            _ => (exp * self.ln()).exp(),
+        }
+    }
+}
+
+impl System {
+    /// Columns of the Jacobian of `block` over the whole box, by derivatives along each of its
+    /// variables
+    fn interval_jacobian(
+        &self,
+        boxes: &[ComplexInterval],
+        block: &Block,
+    ) -> Vec<Vec<ComplexInterval>> {
+        let mut args: Vec<Dual> = boxes.iter().map(|&b| Dual::constant(b)).collect();
+        block
+            .vars
+            .iter()
+            .map(|&var| {
+                args[var].d = ComplexInterval::from_f64(1.0);
+                let column = self.block_run(&args, block).iter().map(|v| v.d).collect();
+                args[var].d = ComplexInterval::from_f64(0.0);
+                column
+            })
+            .collect()
+    }
+
+    /// Jacobian by differentiating the equations, exact up to rounding unlike finite
+    /// differences. Falls back to those where there is no complex derivative, as of `|x|`.
+    pub fn exact_jacobian(&self, args: &[Complex64]) -> DMatrix<Complex64> {
+        self.exact_block_jacobian(args, &self.whole_block())
+    }
+
+    /// `exact_jacobian` of the equations and variables of `block`
+    pub fn exact_block_jacobian(&self, args: &[Complex64], block: &Block) -> DMatrix<Complex64> {
+        let center: Vec<ComplexInterval> =
+            args.iter().map(|&v| ComplexInterval::point(v)).collect();
+        let columns = self.interval_jacobian(&center, block);
+        let out = DMatrix::from_fn(block.eqs.len(), block.vars.len(), |i, j| {
+            columns[j][i].mid()
+        });
+        if out.iter().all(|v| v.is_finite()) {
+            out
+        } else {
+            self.block_jacobian(args, block)
+        }
+    }
+
+    /// Proves with the Krawczyk test that a small box around `solution` holds exactly one
+    /// root of the system, and returns a box that holds it. The boxes are complex, or real
+    /// ones when the branch takes real roots, as those jump across the real axis. Returns
+    /// `None` if no box passes, which includes systems that are not square, roots where the
+    /// Jacobian is singular and complex ones when taking real roots.
+    pub fn certify(&self, solution: &[Complex64]) -> Option<Vec<ComplexInterval>> {
+        let n = self.var_amount;
+        let real = self.branch.real_roots;
+        if self.eqs.len() != n
+            || solution.len() != n
+            || real && solution.iter().any(|v| v.im != 0.0)
+        {
+            return None;
+        }
+        let center: Vec<ComplexInterval> = solution
+            .iter()
+            .map(|&v| ComplexInterval::point(v))
+            .collect();
+        // any matrix works here, but the closer to the inverse Jacobian the smaller the box
+        let y = self.exact_jacobian(solution).try_inverse()?;
+        let y = |i: usize, j: usize| ComplexInterval::point(y[(i, j)]);
+
+        let residual = self.run(&center);
+        let newton: Vec<ComplexInterval> = (0..n)
+            .map(|i| {
+                (0..n).fold(ComplexInterval::from_f64(0.0), |s, k| {
+                    s + y(i, k) * residual[k]
+                })
+            })
+            .collect();
+
+        CERTIFY_RADII.iter().find_map(|relative| {
+            let boxes: Vec<ComplexInterval> = (0..n)
+                .map(|i| {
+                    let radius = relative * (1.0 + solution[i].norm()) + 2.0 * newton[i].mag();
+                    let around = ComplexInterval::around(solution[i], radius);
+                    if real {
+                        ComplexInterval::new(around.re, Interval::ZERO)
+                    } else {
+                        around
+                    }
+                })
+                .collect();
+
+            let jacobian = self.interval_jacobian(&boxes, &self.whole_block());
+            // K = x - Y f(x) + (I - Y J(X)) (X - x), which holds a unique root of the box if
+            // it lies inside it
+            let krawczyk: Vec<ComplexInterval> = (0..n)
+                .map(|i| {
+                    (0..n).fold(center[i] - newton[i], |sum, j| {
+                        let y_j = (0..n).fold(ComplexInterval::from_f64(0.0), |s, k| {
+                            s + y(i, k) * jacobian[j][k]
+                        });
+                        let identity = ComplexInterval::from_f64(if i == j { 1.0 } else { 0.0 });
+                        sum + (identity - y_j) * (boxes[j] - center[j])
+                    })
+                })
+                .collect();
+            krawczyk
+                .iter()
+                .zip(&boxes)
+                .all(|(k, b)| {
+                    if real {
+                        k.re.is_interior(b.re) && k.im == Interval::ZERO
+                    } else {
+                        k.is_interior(*b)
+                    }
+                })
+                .then_some(krawczyk)
+        })
+    }
+}
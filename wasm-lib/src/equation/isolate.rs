@@ -0,0 +1,143 @@
+use crate::{
+    parsing::{
+        ast::{ExprNode, Function},
+        operators::{BinOp, UnaryOp},
+        symbols::VarId,
+    },
+    Error,
+};
+
+use super::{
+    running::Branch,
+    system::{Equation, System},
+};
+
+fn bin_op(a: ExprNode, op: BinOp, b: ExprNode) -> ExprNode {
+    ExprNode::BinOp(Box::new(a), op, Box::new(b))
+}
+
+fn func(f: Function, v: ExprNode) -> ExprNode {
+    ExprNode::Func(f, Box::new(v))
+}
+
+impl ExprNode {
+    /// How many times variable `id` appears in the expression
+    pub fn var_count(&self, id: VarId) -> usize {
+        match self {
+            ExprNode::Var(v) => (*v == id) as usize,
+            ExprNode::Number(_)
+            | ExprNode::Decimal(..)
+            | ExprNode::E
+            | ExprNode::Pi
+            | ExprNode::I
+            | ExprNode::Unit(_) => 0,
+            ExprNode::BinOp(a, _, b) => a.var_count(id) + b.var_count(id),
+            ExprNode::UnaryOp(_, v) | ExprNode::Abs(v) | ExprNode::Func(_, v) => v.var_count(id),
+        }
+    }
+}
+
+/// Inverse of `f`, such that `f(x) = c` gives `x = inverse(c)`
+fn inverse(f: Function, c: ExprNode) -> Option<ExprNode> {
+    Some(match f {
+        Function::Sin => func(Function::Asin, c),
+        Function::Cos => func(Function::Acos, c),
+        Function::Tan => func(Function::Atan, c),
+        Function::SinH => func(Function::AsinH, c),
+        Function::CosH => func(Function::AcosH, c),
+        Function::TanH => func(Function::AtanH, c),
+        Function::Asin => func(Function::Sin, c),
+        Function::Acos => func(Function::Cos, c),
+        Function::Atan => func(Function::Tan, c),
+        Function::AsinH => func(Function::SinH, c),
+        Function::AcosH => func(Function::CosH, c),
+        Function::AtanH => func(Function::TanH, c),
+        Function::Sqrt => bin_op(c, BinOp::Pow, ExprNode::Number(2.0)),
+        Function::Cbrt => bin_op(c, BinOp::Pow, ExprNode::Number(3.0)),
+        Function::Ln => bin_op(ExprNode::E, BinOp::Pow, c),
+        Function::Arg => return None,
+    })
+}
+
+impl Equation {
+    /// Rearranges the equation into a formula for variable `var` by inverting every operation
+    /// around it, which only works if it appears once. Inverses of multivalued functions
+    /// follow the `branch` the formula is evaluated with.
+    pub fn isolate(&self, var: VarId, branch: Branch) -> Result<ExprNode, String> {
+        let (mut expr, mut other) = (self.left.clone(), self.right.clone());
+        if expr.var_count(var) + other.var_count(var) > 1 {
+            // like terms might merge into a single occurrence
+            expr = bin_op(expr, BinOp::Minus, other).simplify(branch);
+            other = ExprNode::Number(0.0);
+        }
+        match (expr.var_count(var), other.var_count(var)) {
+            (0, 0) => return Err("The variable does not appear in the equation".into()),
+            (0, 1) => std::mem::swap(&mut expr, &mut other),
+            (1, 0) => (),
+            _ => return Err("Cannot isolate a variable that appears more than once".into()),
+        }
+
+        loop {
+            (expr, other) = match expr {
+                ExprNode::Var(_) => return Ok(other.simplify(branch)),
+                ExprNode::BinOp(a, op, b) => {
+                    let in_left = a.var_count(var) == 1;
+                    match (op, in_left) {
+                        (BinOp::Plus, true) => (*a, bin_op(other, BinOp::Minus, *b)),
+                        (BinOp::Plus, false) => (*b, bin_op(other, BinOp::Minus, *a)),
+                        (BinOp::Minus, true) => (*a, bin_op(other, BinOp::Plus, *b)),
+                        (BinOp::Minus, false) => (*b, bin_op(*a, BinOp::Minus, other)),
+                        (BinOp::Mult, true) => (*a, bin_op(other, BinOp::Div, *b)),
+                        (BinOp::Mult, false) => (*b, bin_op(other, BinOp::Div, *a)),
+                        (BinOp::Div, true) => (*a, bin_op(other, BinOp::Mult, *b)),
+                        (BinOp::Div, false) => (*b, bin_op(*a, BinOp::Div, other)),
+                        (BinOp::Pow, true) => {
+                            let exp = bin_op(ExprNode::Number(1.0), BinOp::Div, *b);
+                            (*a, bin_op(other, BinOp::Pow, exp))
+                        }
+                        (BinOp::Pow, false) => {
+                            let ln = |v| func(Function::Ln, v);
+                            (*b, bin_op(ln(other), BinOp::Div, ln(*a)))
+                        }
+                        (BinOp::Mod, _) => return Err("Cannot invert `%`".into()),
+                    }
+                }
+                ExprNode::UnaryOp(UnaryOp::Plus, v) => (*v, other),
+                ExprNode::UnaryOp(UnaryOp::Minus, v) => {
+                    (*v, ExprNode::UnaryOp(UnaryOp::Minus, Box::new(other)))
+                }
+                ExprNode::Func(f, v) => match inverse(f, other) {
+                    Some(other) => (*v, other),
+                    None => return Err(format!("Cannot invert `{}`", f.name())),
+                },
+                ExprNode::Abs(_) => return Err("Cannot invert `|...|`".into()),
+                ExprNode::Number(_)
+                | ExprNode::Decimal(..)
+                | ExprNode::E
+                | ExprNode::Pi
+                | ExprNode::I
+                | ExprNode::Unit(_) => unreachable!(),
+            }
+        }
+    }
+}
+
+impl System {
+    /// Rearranges equation `eq` into a formula for the variable named `var`, following the
+    /// branch of the system
+    pub fn isolate(&self, eq: usize, var: &str) -> Result<ExprNode, Error> {
+        let Some(equation) = self.eqs.get(eq) else {
+            // counted from 0, like the equations of `PositionedError`
+            return Err(Error::InvalidInput(match self.eqs.len() {
+                0 => "The system has no equations".to_string(),
+                n => format!("There is no equation {}, only 0 to {}", eq, n - 1),
+            }));
+        };
+        let Some(id) = self.symbols.id(var) else {
+            return Err(Error::UnknownVariable(var.to_string()));
+        };
+        equation
+            .isolate(id, self.branch)
+            .map_err(|msg| Error::NotIsolatable { eq, msg })
+    }
+}
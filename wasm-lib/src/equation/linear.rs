@@ -0,0 +1,189 @@
+use nalgebra::{DMatrix, DVector};
+use num_complex::Complex64;
+
+use crate::parsing::{
+    ast::ExprNode,
+    operators::{BinOp, UnaryOp},
+};
+
+use super::{running::Branch, system::System};
+
+/// Singular values below this, relative to the largest one, count as zero
+const RANK_TOLERANCE: f64 = 1e-10;
+
+/// `coefs · vars + constant`
+struct Affine {
+    coefs: Vec<Complex64>,
+    constant: Complex64,
+}
+
+#[derive(Debug)]
+pub enum LinearSolution {
+    Unique(Vec<Complex64>),
+    None,
+    /// Every `particular + Σ cᵢ null_space[i]` is a solution
+    Infinite {
+        particular: Vec<Complex64>,
+        null_space: Vec<Vec<Complex64>>,
+    },
+}
+
+impl Affine {
+    fn constant(var_amount: usize, v: Complex64) -> Self {
+        Affine {
+            coefs: vec![0.0.into(); var_amount],
+            constant: v,
+        }
+    }
+    fn as_constant(&self) -> Option<Complex64> {
+        if self.coefs.iter().all(|c| *c == 0.0.into()) {
+            Some(self.constant)
+        } else {
+            None
+        }
+    }
+    fn scale(mut self, s: Complex64) -> Self {
+        self.coefs.iter_mut().for_each(|c| *c *= s);
+        self.constant *= s;
+        self
+    }
+    fn add(mut self, other: Affine, sign: f64) -> Self {
+        for (c, o) in self.coefs.iter_mut().zip(other.coefs) {
+            *c += o * sign
+        }
+        self.constant += other.constant * sign;
+        self
+    }
+}
+
+impl ExprNode {
+    /// The expression as an affine function of the variables, if it is one
+    fn affine(&self, var_amount: usize, branch: Branch) -> Option<Affine> {
+        if self.is_constant() {
+            return Some(Affine::constant(var_amount, self.run(&[], branch)));
+        }
+        Some(match self {
+            ExprNode::Var(id) => {
+                let mut out = Affine::constant(var_amount, 0.0.into());
+                out.coefs[*id as usize] = 1.0.into();
+                out
+            }
+            ExprNode::BinOp(a, op, b) => {
+                let a = a.affine(var_amount, branch)?;
+                let b = b.affine(var_amount, branch)?;
+                match op {
+                    BinOp::Plus => a.add(b, 1.0),
+                    BinOp::Minus => a.add(b, -1.0),
+                    BinOp::Mult => match (a.as_constant(), b.as_constant()) {
+                        (Some(a), _) => b.scale(a),
+                        (_, Some(b)) => a.scale(b),
+                        _ => return None,
+                    },
+                    BinOp::Div => a.scale(1.0 / b.as_constant()?),
+                    BinOp::Pow if b.as_constant()? == 1.0.into() => a,
+                    BinOp::Pow | BinOp::Mod => return None,
+                }
+            }
+            ExprNode::UnaryOp(op, v) => {
+                let v = v.affine(var_amount, branch)?;
+                match op {
+                    UnaryOp::Plus => v,
+                    UnaryOp::Minus => v.scale((-1.0).into()),
+                }
+            }
+            _ => return None,
+        })
+    }
+}
+
+impl System {
+    /// The coefficient matrix `A` and right-hand side `b` of `A x = b`, if every equation is linear
+    /// with finite coefficients
+    pub fn linear(&self) -> Option<(DMatrix<Complex64>, DVector<Complex64>)> {
+        let mut rows = vec![];
+        for eq in &self.eqs {
+            let left = eq.left.affine(self.var_amount, self.branch)?;
+            let right = eq.right.affine(self.var_amount, self.branch)?;
+            let row = left.add(right, -1.0);
+            // e.g. `x / 0`, which Newton's method reports as not converging
+            if !row.constant.is_finite() || row.coefs.iter().any(|c| !c.is_finite()) {
+                return None;
+            }
+            rows.push(row);
+        }
+
+        let a = DMatrix::from_fn(self.eqs.len(), self.var_amount, |i, j| rows[i].coefs[j]);
+        let b = DVector::from_iterator(self.eqs.len(), rows.iter().map(|r| -r.constant));
+        Some((a, b))
+    }
+
+    /// Solves the system directly if it is linear
+    pub fn solve_linear(&self) -> Option<LinearSolution> {
+        let (a, b) = self.linear()?;
+        let n = self.var_amount;
+
+        if n == 0 {
+            return Some(if b.iter().all(|v| v.norm() < RANK_TOLERANCE) {
+                LinearSolution::Unique(vec![])
+            } else {
+                LinearSolution::None
+            });
+        }
+
+        if a.is_square() {
+            let lu = a.clone().lu();
+            let pivots = lu.u().diagonal().map(|v| v.norm());
+            if pivots.min() > RANK_TOLERANCE * pivots.max() {
+                if let Some(x) = lu.solve(&b) {
+                    return Some(LinearSolution::Unique(x.iter().copied().collect()));
+                }
+            }
+        }
+
+        // pad with zero rows, so the SVD has a full `n × n` right factor holding the null space
+        let rows = a.nrows().max(n);
+        let padded = DMatrix::from_fn(
+            rows,
+            n,
+            |i, j| {
+                if i < a.nrows() {
+                    a[(i, j)]
+                } else {
+                    0.0.into()
+                }
+            },
+        );
+        let svd = padded.svd(true, true);
+        let v_t = svd.v_t.as_ref().unwrap();
+        let max = svd.singular_values.max();
+        let tolerance = RANK_TOLERANCE * max * rows as f64;
+
+        let mut b_padded = DVector::zeros(rows);
+        b_padded.rows_mut(0, b.len()).copy_from(&b);
+        let particular = svd.solve(&b_padded, tolerance).ok()?;
+
+        // as small as rounding leaves, relative to the size of the terms
+        let residual = (&a * &particular - &b).norm();
+        if residual > tolerance * particular.norm() + RANK_TOLERANCE * rows as f64 * b.norm() {
+            return Some(LinearSolution::None);
+        }
+
+        let null_space: Vec<Vec<Complex64>> = svd
+            .singular_values
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| **s <= tolerance)
+            .map(|(i, _)| v_t.row(i).iter().map(|v| v.conj()).collect())
+            .collect();
+        let particular = particular.iter().copied().collect();
+
+        Some(if null_space.is_empty() {
+            LinearSolution::Unique(particular)
+        } else {
+            LinearSolution::Infinite {
+                particular,
+                null_space,
+            }
+        })
+    }
+}
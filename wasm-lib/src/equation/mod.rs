@@ -1,2 +1,13 @@
+pub mod continuation;
+pub mod dimensions;
+pub mod double_double;
+pub mod interval;
+pub mod isolate;
+pub mod linear;
+pub mod polynomial;
 pub mod running;
+pub mod scalar;
+pub mod simplify;
+pub mod structure;
 pub mod system;
+pub mod verification;
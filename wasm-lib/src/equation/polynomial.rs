@@ -0,0 +1,244 @@
+use nalgebra::{DMatrix, DVector};
+use num_complex::Complex64;
+
+use crate::parsing::{
+    ast::ExprNode,
+    operators::{BinOp, UnaryOp},
+};
+
+use super::{
+    continuation::{track, Homotopy},
+    running::Branch,
+    system::System,
+};
+
+/// Most start solutions tracked, since their amount is the product of the degrees
+const MAX_PATHS: usize = 4096;
+/// Generic constant of the homotopy, avoiding singular paths for almost every system
+const GAMMA_ANGLE: f64 = 2.1462;
+/// Path endpoints closer than this, relative to their size, are the same solution
+const SAME_SOLUTION: f64 = 1e-6;
+/// Newton iterations only converge linearly to singular roots, so their ends lie further apart
+const SAME_SINGULAR_SOLUTION: f64 = 1e-3;
+/// Furthest Newton iterations may move a path's end, which is not exactly on a singular root
+const MAX_POLISH: f64 = 0.1;
+/// Jacobians with a condition number above this count as singular, as the finite difference
+/// Jacobian cannot resolve much smaller singular values
+const MAX_CONDITION: f64 = 1e5;
+
+/// Evidence that a solution is an isolated root
+#[derive(Debug, Clone)]
+pub struct Certificate {
+    /// Norm of the residual after polishing
+    pub residual: f64,
+    /// Distance that Newton iterations moved the end of the path to reach the root
+    pub newton_step: f64,
+    /// Condition number of the Jacobian, finite for isolated regular roots
+    pub condition: f64,
+}
+
+impl Certificate {
+    pub fn is_regular(&self) -> bool {
+        self.condition < MAX_CONDITION
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PolynomialRoot {
+    pub solution: Vec<Complex64>,
+    pub certificate: Certificate,
+    /// How many paths ended at this root, which is its multiplicity when the paths are regular
+    pub paths: usize,
+}
+
+#[derive(Debug)]
+pub struct PolynomialSolutions {
+    pub roots: Vec<PolynomialRoot>,
+    /// Amount of paths tracked, the Bézout bound of the system
+    pub paths: usize,
+    /// Paths that went to infinity or could not be followed
+    pub lost: usize,
+}
+
+/// Distance between two points
+fn distance(a: &[Complex64], b: &[Complex64]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).norm_sqr())
+        .sum::<f64>()
+        .sqrt()
+}
+
+impl ExprNode {
+    /// Total degree of the expression if it is a polynomial in the variables, and no more than
+    /// the most paths that can be tracked
+    pub fn degree(&self, branch: Branch) -> Option<u32> {
+        if self.is_constant() {
+            return Some(0);
+        }
+        let degree = match self {
+            ExprNode::Var(_) => 1,
+            ExprNode::BinOp(a, op, b) => match op {
+                BinOp::Plus | BinOp::Minus => a.degree(branch)?.max(b.degree(branch)?),
+                BinOp::Mult => a.degree(branch)?.checked_add(b.degree(branch)?)?,
+                BinOp::Div if b.is_constant() => a.degree(branch)?,
+                BinOp::Pow if b.is_constant() => {
+                    let exp: Complex64 = b.run(&[], branch);
+                    if exp.im != 0.0 || exp.re < 0.0 || exp.re.fract() != 0.0 {
+                        return None;
+                    }
+                    // a larger exponent of a non-constant base is past the limit anyway
+                    if exp.re > MAX_PATHS as f64 {
+                        return None;
+                    }
+                    a.degree(branch)?.checked_mul(exp.re as u32)?
+                }
+                _ => return None,
+            },
+            ExprNode::UnaryOp(UnaryOp::Plus | UnaryOp::Minus, v) => v.degree(branch)?,
+            _ => return None,
+        };
+        (degree as usize <= MAX_PATHS).then_some(degree)
+    }
+}
+
+/// `(1 - t) γ G(x) + t F(x)`, where `G(x)ᵢ = xᵢ^dᵢ - 1` has the same degrees as `F`
+struct TotalDegreeHomotopy<'a> {
+    system: &'a System,
+    degrees: Vec<u32>,
+    gamma: Complex64,
+}
+
+impl TotalDegreeHomotopy<'_> {
+    fn start(&self, x: &[Complex64]) -> DVector<Complex64> {
+        DVector::from_iterator(
+            x.len(),
+            x.iter().zip(&self.degrees).map(|(x, d)| x.powu(*d) - 1.0),
+        )
+    }
+}
+
+impl Homotopy for TotalDegreeHomotopy<'_> {
+    fn eval(&self, x: &[Complex64], t: f64) -> DVector<Complex64> {
+        self.start(x) * (self.gamma * (1.0 - t)) + self.system.run(x) * Complex64::from(t)
+    }
+    fn jacobian(&self, x: &[Complex64], t: f64) -> DMatrix<Complex64> {
+        let start = DMatrix::from_diagonal(&DVector::from_iterator(
+            x.len(),
+            x.iter()
+                .zip(&self.degrees)
+                .map(|(x, d)| x.powu(d - 1) * *d as f64),
+        ));
+        start * (self.gamma * (1.0 - t)) + self.system.jacobian(x) * Complex64::from(t)
+    }
+    fn dt(&self, x: &[Complex64], _: f64) -> DVector<Complex64> {
+        self.system.run(x) - self.start(x) * self.gamma
+    }
+}
+
+impl System {
+    /// Every equation's degree, if the system is a polynomial one
+    pub fn degrees(&self) -> Option<Vec<u32>> {
+        self.eqs
+            .iter()
+            .map(|eq| {
+                Some(
+                    eq.left
+                        .degree(self.branch)?
+                        .max(eq.right.degree(self.branch)?),
+                )
+            })
+            .collect()
+    }
+
+    /// Condition number of the Jacobian, taking its norm to be at least 1 so a Jacobian that is
+    /// zero everywhere still counts as singular
+    pub fn condition(&self, args: &[Complex64]) -> f64 {
+        let singular_values = self.jacobian(args).singular_values();
+        let min = singular_values.min();
+        if min == 0.0 {
+            f64::INFINITY
+        } else {
+            singular_values.max().max(1.0) / min
+        }
+    }
+
+    /// Finds every isolated root of a square polynomial system by tracking the roots of
+    /// `xᵢ^dᵢ = 1` for its equation degrees `dᵢ` along a total-degree homotopy, or `None` if
+    /// the system is not polynomial, not square or has too many paths
+    pub fn solve_polynomial(&self, iter: usize) -> Option<PolynomialSolutions> {
+        let degrees = self.degrees()?;
+        if degrees.len() != self.var_amount || degrees.contains(&0) {
+            return None;
+        }
+        let paths = degrees
+            .iter()
+            .try_fold(1usize, |acc, d| acc.checked_mul(*d as usize))
+            .filter(|p| *p <= MAX_PATHS)?;
+
+        let h = TotalDegreeHomotopy {
+            system: self,
+            degrees: degrees.clone(),
+            gamma: Complex64::from_polar(1.0, GAMMA_ANGLE),
+        };
+
+        let mut out = PolynomialSolutions {
+            roots: vec![],
+            paths,
+            lost: 0,
+        };
+        for path in 0..paths {
+            // the `path`th combination of roots of unity, as digits in mixed radix
+            let mut rest = path;
+            let start = degrees
+                .iter()
+                .map(|&d| {
+                    let k = rest % d as usize;
+                    rest /= d as usize;
+                    Complex64::from_polar(1.0, std::f64::consts::TAU * k as f64 / d as f64)
+                })
+                .collect();
+
+            let Some(mut solution) = track(&h, start) else {
+                out.lost += 1;
+                continue;
+            };
+            let before = solution.clone();
+            if !self.solve_block(&mut solution, &self.whole_block(), iter) {
+                out.lost += 1;
+                continue;
+            }
+
+            let size = distance(&solution, &vec![Complex64::default(); solution.len()]);
+            let newton_step = distance(&before, &solution);
+            // a path going to infinity can end anywhere and then be polished onto another root
+            if newton_step > MAX_POLISH * (1.0 + size) {
+                out.lost += 1;
+                continue;
+            }
+            let certificate = Certificate {
+                residual: self.run(&solution).norm(),
+                newton_step,
+                condition: self.condition(&solution),
+            };
+
+            let same = |root: &&mut PolynomialRoot| {
+                let tolerance = if certificate.is_regular() && root.certificate.is_regular() {
+                    SAME_SOLUTION
+                } else {
+                    SAME_SINGULAR_SOLUTION
+                };
+                distance(&root.solution, &solution) < tolerance * (1.0 + size)
+            };
+            match out.roots.iter_mut().find(same) {
+                Some(root) => root.paths += 1,
+                None => out.roots.push(PolynomialRoot {
+                    solution,
+                    certificate,
+                    paths: 1,
+                }),
+            }
+        }
+        Some(out)
+    }
+}
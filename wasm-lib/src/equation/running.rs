@@ -1,42 +1,145 @@
-use num_complex::{Complex64, ComplexFloat};
-
 use crate::parsing::{
     ast::{ExprNode, Function},
     operators::{BinOp, UnaryOp},
 };
 
-use super::system::{Equation, System};
+use super::{scalar::Scalar, system::Equation};
+
+/// Largest odd denominator checked when looking for real odd roots
+const MAX_ROOT_DENOM: i64 = 99;
+
+/// Selects which branch of the multivalued functions is evaluated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Branch {
+    /// Odd roots of negative reals give the real root, e.g. `(-8)^(1/3) = -2` and `cbrt(-8) = -2`
+    pub real_roots: bool,
+    /// Branch index of `ln`, powers, roots and inverse functions, `0` being the principal branch.
+    /// Real roots taken because of `real_roots` have no other branches, so they ignore it.
+    pub k: i32,
+}
+
+impl Branch {
+    /// `v + step * k`
+    fn offset<T: Scalar>(self, v: T, step: T) -> T {
+        if self.k == 0 {
+            return v;
+        }
+        v + step * T::from_f64(self.k as f64)
+    }
+    /// `v * (-1)^k`
+    fn signed<T: Scalar>(self, v: T) -> T {
+        if self.k % 2 == 0 {
+            v
+        } else {
+            -v
+        }
+    }
+    /// `2πi`
+    fn full_turn<T: Scalar>() -> T {
+        let i_pi = T::i() * T::pi();
+        i_pi + i_pi
+    }
+    /// `e^(2πik * frac)`, the factor between the principal and the `k`th branch of `z^frac`
+    fn rotation<T: Scalar>(self, frac: T) -> T {
+        self.offset(T::from_f64(0.0), Branch::full_turn::<T>() * frac)
+            .exp()
+    }
+
+    pub fn ln<T: Scalar>(self, v: T) -> T {
+        self.offset(v.ln(), Branch::full_turn())
+    }
+    pub fn pow<T: Scalar>(self, a: T, b: T) -> T {
+        let (approx_a, approx_b) = (a.to_complex(), b.to_complex());
+        if self.real_roots && approx_a.im == 0.0 && approx_a.re < 0.0 && approx_b.im == 0.0 {
+            if let Some(p) = odd_root_numerator(approx_b.re) {
+                let v = (-a).real_powf(b);
+                return if p % 2 == 0 { v } else { -v };
+            }
+        }
+        if self.k == 0 {
+            return a.powc(b);
+        }
+        a.powc(b) * self.rotation(b)
+    }
+    pub fn sqrt<T: Scalar>(self, v: T) -> T {
+        self.signed(v.sqrt())
+    }
+    /// With `real_roots`, every real `v` gives its real cube root, whatever `k` is
+    pub fn cbrt<T: Scalar>(self, v: T) -> T {
+        if self.real_roots && v.to_complex().im == 0.0 {
+            return v.real_cbrt();
+        }
+        let third = T::from_f64(1.0) / T::from_f64(3.0);
+        v.cbrt() * self.rotation(third)
+    }
+
+    pub fn asin<T: Scalar>(self, v: T) -> T {
+        self.offset(self.signed(v.asin()), T::pi())
+    }
+    pub fn acos<T: Scalar>(self, v: T) -> T {
+        T::pi() / T::from_f64(2.0) - self.asin(v)
+    }
+    pub fn atan<T: Scalar>(self, v: T) -> T {
+        self.offset(v.atan(), T::pi())
+    }
+    pub fn asinh<T: Scalar>(self, v: T) -> T {
+        self.offset(self.signed(v.asinh()), T::i() * T::pi())
+    }
+    pub fn acosh<T: Scalar>(self, v: T) -> T {
+        self.offset(v.acosh(), Branch::full_turn())
+    }
+    pub fn atanh<T: Scalar>(self, v: T) -> T {
+        self.offset(v.atanh(), T::i() * T::pi())
+    }
+}
+
+/// If `exp` is `p/q` with an odd `q`, returns `p`
+fn odd_root_numerator(exp: f64) -> Option<i64> {
+    (1..=MAX_ROOT_DENOM).step_by(2).find_map(|q| {
+        let p = exp * q as f64;
+        if (p - p.round()).abs() < 1e-9 {
+            Some(p.round() as i64)
+        } else {
+            None
+        }
+    })
+}
 
 impl ExprNode {
-    pub fn run(&self, args: &[Complex64]) -> Complex64 {
+    /// The value of the expression, with `args[id]` for every variable `id`
+    pub fn run<T: Scalar>(&self, args: &[T], branch: Branch) -> T {
         match self {
-            ExprNode::Number(v) => v.into(),
+            ExprNode::Number(v) => T::from_f64(*v),
+            ExprNode::Decimal(_, digits) => T::from_decimal(digits),
             ExprNode::Var(id) => args[*id as usize],
             ExprNode::BinOp(a, op, b) => {
-                let a = a.run(args);
-                let b = b.run(args);
+                let a = a.run(args, branch);
+                let b = b.run(args, branch);
                 match op {
                     BinOp::Plus => a + b,
                     BinOp::Minus => a - b,
                     BinOp::Mult => a * b,
                     BinOp::Div => a / b,
                     BinOp::Mod => a % b,
-                    BinOp::Pow => a.powc(b),
+                    BinOp::Pow => branch.pow(a, b),
                 }
             }
             ExprNode::UnaryOp(op, v) => {
-                let v = v.run(args);
+                let v = v.run(args, branch);
                 match op {
                     UnaryOp::Plus => v,
-                    UnaryOp::Minus => -v,
+                    // subtracting from zero keeps negated reals at `+0i`, so they
+                    // land on the principal side of branch cuts
+                    UnaryOp::Minus => T::from_f64(0.0) - v,
                 }
             }
-            ExprNode::E => std::f64::consts::E.into(),
-            ExprNode::Pi => std::f64::consts::PI.into(),
-            ExprNode::I => Complex64::i(),
-            ExprNode::Abs(v) => v.run(args).abs().into(),
+            ExprNode::E => T::e(),
+            ExprNode::Pi => T::pi(),
+            ExprNode::I => T::i(),
+            ExprNode::Unit(unit) => T::from_f64(unit.scale),
+            ExprNode::Abs(v) => v.run(args, branch).abs(),
             ExprNode::Func(f, v) => {
-                let v = v.run(args);
+                let v = v.run(args, branch);
                 match f {
                     Function::Sin => v.sin(),
                     Function::Cos => v.cos(),
@@ -44,16 +147,16 @@ impl ExprNode {
                     Function::SinH => v.sinh(),
                     Function::CosH => v.cosh(),
                     Function::TanH => v.tanh(),
-                    Function::Asin => v.asin(),
-                    Function::Acos => v.acos(),
-                    Function::Atan => v.atan(),
-                    Function::AsinH => v.asinh(),
-                    Function::AcosH => v.acosh(),
-                    Function::AtanH => v.atanh(),
-                    Function::Ln => v.ln(),
-                    Function::Sqrt => v.sqrt(),
-                    Function::Cbrt => v.cbrt(),
-                    Function::Arg => v.arg().into(),
+                    Function::Asin => branch.asin(v),
+                    Function::Acos => branch.acos(v),
+                    Function::Atan => branch.atan(v),
+                    Function::AsinH => branch.asinh(v),
+                    Function::AcosH => branch.acosh(v),
+                    Function::AtanH => branch.atanh(v),
+                    Function::Ln => branch.ln(v),
+                    Function::Sqrt => branch.sqrt(v),
+                    Function::Cbrt => branch.cbrt(v),
+                    Function::Arg => v.arg(),
                 }
             }
         }
@@ -61,7 +164,11 @@ impl ExprNode {
 }
 
 impl Equation {
-    pub fn calc(&self, args: &[Complex64]) -> Complex64 {
-        self.left.run(args) - self.right.run(args)
+    pub fn calc<T: Scalar>(&self, args: &[T], branch: Branch) -> T {
+        let (left, right) = self.sides(args, branch);
+        left - right
+    }
+    pub fn sides<T: Scalar>(&self, args: &[T], branch: Branch) -> (T, T) {
+        (self.left.run(args, branch), self.right.run(args, branch))
     }
 }
@@ -0,0 +1,237 @@
+use std::{
+    f64::consts,
+    fmt::Debug,
+    ops::{Add, Div, Mul, Neg, Rem, Sub},
+};
+
+use num_complex::Complex64;
+
+use super::double_double::{ComplexDD, DoubleDouble};
+
+/// Numbers that expressions can be evaluated in with `ExprNode::run`, with the arithmetic and
+/// the principal branches of the functions of complex numbers. `Branch` picks the other
+/// branches from these.
+///
+/// Only the constructors, `abs`, `arg`, `exp`, `ln`, `sqrt`, `sin` and `cos` have to be given.
+/// The rest default to the formulas `num_complex` uses, some of which need `i`, so types that
+/// cannot hold `i` should give those too.
+pub trait Scalar:
+    Copy
+    + PartialEq
+    + Debug
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + Neg<Output = Self>
+{
+    fn from_f64(v: f64) -> Self;
+    /// The number written as the decimal `digits`, like `0.1`, which the nearest `f64` does
+    /// for types no more precise than it
+    fn from_decimal(digits: &str) -> Self {
+        Self::from_f64(digits.parse().unwrap_or(f64::NAN))
+    }
+    /// The nearest `Complex64`, which decides the branch of real roots
+    fn to_complex(self) -> Complex64;
+    fn i() -> Self;
+    fn e() -> Self {
+        Self::from_f64(consts::E)
+    }
+    fn pi() -> Self {
+        Self::from_f64(consts::PI)
+    }
+
+    /// The modulus, as a real number
+    fn abs(self) -> Self;
+    /// The argument, as a real number
+    fn arg(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+
+    fn powc(self, exp: Self) -> Self {
+        (exp * self.ln()).exp()
+    }
+    fn cbrt(self) -> Self {
+        self.powc(Self::from_f64(1.0) / Self::from_f64(3.0))
+    }
+    /// `self^exp` of a real `self >= 0` and a real `exp`
+    fn real_powf(self, exp: Self) -> Self {
+        self.powc(exp)
+    }
+    /// Real cube root of a real number
+    fn real_cbrt(self) -> Self {
+        let third = Self::from_f64(1.0) / Self::from_f64(3.0);
+        if self.to_complex().re < 0.0 {
+            -(-self).real_powf(third)
+        } else {
+            self.real_powf(third)
+        }
+    }
+
+    fn tan(self) -> Self {
+        self.sin() / self.cos()
+    }
+    fn sinh(self) -> Self {
+        (self.exp() - (-self).exp()) / Self::from_f64(2.0)
+    }
+    fn cosh(self) -> Self {
+        (self.exp() + (-self).exp()) / Self::from_f64(2.0)
+    }
+    fn tanh(self) -> Self {
+        self.sinh() / self.cosh()
+    }
+    fn asin(self) -> Self {
+        let one = Self::from_f64(1.0);
+        -Self::i() * ((one - self * self).sqrt() + Self::i() * self).ln()
+    }
+    fn atan(self) -> Self {
+        let (one, i) = (Self::from_f64(1.0), Self::i());
+        ((one + i * self).ln() - (one - i * self).ln()) / (i + i)
+    }
+    fn asinh(self) -> Self {
+        (self + (Self::from_f64(1.0) + self * self).sqrt()).ln()
+    }
+    fn acosh(self) -> Self {
+        let (one, two) = (Self::from_f64(1.0), Self::from_f64(2.0));
+        two * (((self + one) / two).sqrt() + ((self - one) / two).sqrt()).ln()
+    }
+    fn atanh(self) -> Self {
+        let one = Self::from_f64(1.0);
+        ((one + self).ln() - (one - self).ln()) / Self::from_f64(2.0)
+    }
+}
+
+/// Implements the functions of `Scalar` by the methods of the same name
+macro_rules! scalar_functions {
+    ($($f:ident),*) => {
+        $(fn $f(self) -> Self {
+            self.$f()
+        })*
+    };
+}
+
+impl Scalar for Complex64 {
+    fn from_f64(v: f64) -> Self {
+        v.into()
+    }
+    fn to_complex(self) -> Complex64 {
+        self
+    }
+    fn i() -> Self {
+        Complex64::i()
+    }
+
+    fn abs(self) -> Self {
+        self.norm().into()
+    }
+    fn arg(self) -> Self {
+        Complex64::arg(self).into()
+    }
+    fn powc(self, exp: Self) -> Self {
+        Complex64::powc(self, exp)
+    }
+    fn real_powf(self, exp: Self) -> Self {
+        self.re.powf(exp.re).into()
+    }
+    fn real_cbrt(self) -> Self {
+        self.re.cbrt().into()
+    }
+    scalar_functions!(
+        exp, ln, sqrt, cbrt, sin, cos, tan, sinh, cosh, tanh, asin, atan, asinh, acosh, atanh
+    );
+}
+
+impl Scalar for ComplexDD {
+    fn from_f64(v: f64) -> Self {
+        ComplexDD::new(v.into(), DoubleDouble::ZERO)
+    }
+    fn from_decimal(digits: &str) -> Self {
+        let re = digits.parse().unwrap_or_else(|_| f64::NAN.into());
+        ComplexDD::new(re, DoubleDouble::ZERO)
+    }
+    fn to_complex(self) -> Complex64 {
+        ComplexDD::to_complex(self)
+    }
+    fn i() -> Self {
+        ComplexDD::i()
+    }
+    fn e() -> Self {
+        ComplexDD::new(DoubleDouble::E, DoubleDouble::ZERO)
+    }
+    fn pi() -> Self {
+        ComplexDD::new(DoubleDouble::PI, DoubleDouble::ZERO)
+    }
+
+    fn abs(self) -> Self {
+        ComplexDD::new(self.norm(), DoubleDouble::ZERO)
+    }
+    fn arg(self) -> Self {
+        ComplexDD::new(ComplexDD::arg(self), DoubleDouble::ZERO)
+    }
+    fn powc(self, exp: Self) -> Self {
+        ComplexDD::powc(self, exp)
+    }
+    fn real_powf(self, exp: Self) -> Self {
+        ComplexDD::new((exp.re * self.re.ln()).exp(), DoubleDouble::ZERO)
+    }
+    fn real_cbrt(self) -> Self {
+        ComplexDD::new(self.re.cbrt(), DoubleDouble::ZERO)
+    }
+    scalar_functions!(
+        exp, ln, sqrt, cbrt, sin, cos, tan, sinh, cosh, tanh, asin, atan, asinh, acosh, atanh
+    );
+}
+
+/// Real numbers, for evaluating faster where nothing is complex. Values that would have an
+/// imaginary part are NaN instead, including `i`. Other branches of `sqrt`, `asin`, `acos` and
+/// `atan` are real and keep their value, while those of `ln`, powers, `cbrt` and the inverse
+/// hyperbolic functions are NaN, apart from real roots taken because of `Branch::real_roots`.
+impl Scalar for f64 {
+    fn from_f64(v: f64) -> Self {
+        v
+    }
+    fn to_complex(self) -> Complex64 {
+        self.into()
+    }
+    fn i() -> Self {
+        f64::NAN
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+    fn arg(self) -> Self {
+        if self.is_nan() {
+            self
+        } else if self.is_sign_negative() {
+            consts::PI
+        } else {
+            0.0
+        }
+    }
+    fn powc(self, exp: Self) -> Self {
+        self.powf(exp)
+    }
+    fn cbrt(self) -> Self {
+        // the principal cube root of a negative number is not real
+        if self < 0.0 {
+            f64::NAN
+        } else {
+            f64::cbrt(self)
+        }
+    }
+    fn real_powf(self, exp: Self) -> Self {
+        self.powf(exp)
+    }
+    fn real_cbrt(self) -> Self {
+        f64::cbrt(self)
+    }
+    scalar_functions!(
+        exp, ln, sqrt, sin, cos, tan, sinh, cosh, tanh, asin, atan, asinh, acosh, atanh
+    );
+}
@@ -0,0 +1,352 @@
+use std::cmp::Ordering;
+
+use num_complex::Complex64;
+
+use crate::parsing::{
+    ast::ExprNode,
+    operators::{BinOp, UnaryOp},
+};
+
+use super::{running::Branch, system::System};
+
+/// Sum of terms with complex coefficients, plus a constant
+struct Sum {
+    terms: Vec<(Complex64, ExprNode)>,
+    constant: Complex64,
+}
+
+/// Product of factors with real exponents, times a complex coefficient
+struct Product {
+    coef: Complex64,
+    factors: Vec<(ExprNode, f64)>,
+}
+
+const ONE: Complex64 = Complex64::new(1.0, 0.0);
+const ZERO: Complex64 = Complex64::new(0.0, 0.0);
+
+/// Builds a number node, keeping negative numbers as a negation and writing complex ones as
+/// `a + b*i`
+fn number(v: Complex64) -> ExprNode {
+    let real = |v: f64| {
+        if v < 0.0 {
+            ExprNode::UnaryOp(UnaryOp::Minus, Box::new(ExprNode::Number(-v)))
+        } else {
+            ExprNode::Number(v)
+        }
+    };
+    let imaginary = |v: f64| {
+        if v.abs() == 1.0 {
+            ExprNode::I
+        } else {
+            bin_op(ExprNode::Number(v.abs()), BinOp::Mult, ExprNode::I)
+        }
+    };
+
+    if v.im == 0.0 {
+        real(v.re)
+    } else if v.re == 0.0 && v.im < 0.0 {
+        ExprNode::UnaryOp(UnaryOp::Minus, Box::new(imaginary(v.im)))
+    } else if v.re == 0.0 {
+        imaginary(v.im)
+    } else if v.im < 0.0 {
+        bin_op(real(v.re), BinOp::Minus, imaginary(v.im))
+    } else {
+        bin_op(real(v.re), BinOp::Plus, imaginary(v.im))
+    }
+}
+
+/// Whether `v` is written with a leading minus, like `-2` and `-3i`
+fn is_negative(v: Complex64) -> bool {
+    v.re < 0.0 || (v.re == 0.0 && v.im < 0.0)
+}
+
+fn bin_op(a: ExprNode, op: BinOp, b: ExprNode) -> ExprNode {
+    ExprNode::BinOp(Box::new(a), op, Box::new(b))
+}
+
+impl Sum {
+    fn of(node: ExprNode, branch: Branch) -> Self {
+        let mut sum = Sum {
+            terms: vec![],
+            constant: ZERO,
+        };
+        sum.collect(node, 1.0, branch);
+        sum
+    }
+
+    fn collect(&mut self, node: ExprNode, sign: f64, branch: Branch) {
+        match node {
+            ExprNode::BinOp(a, BinOp::Plus, b) => {
+                self.collect(*a, sign, branch);
+                self.collect(*b, sign, branch);
+            }
+            ExprNode::BinOp(a, BinOp::Minus, b) => {
+                self.collect(*a, sign, branch);
+                self.collect(*b, -sign, branch);
+            }
+            ExprNode::UnaryOp(UnaryOp::Plus, v) => self.collect(*v, sign, branch),
+            ExprNode::UnaryOp(UnaryOp::Minus, v) => self.collect(*v, -sign, branch),
+            node => {
+                if let Some(v) = node.constant_value(branch) {
+                    self.constant += sign * v;
+                    return;
+                }
+                let mut product = Product::of(node, branch);
+                let coef = std::mem::replace(&mut product.coef, ONE);
+                let term = product.build();
+
+                match self.terms.iter_mut().find(|(_, t)| *t == term) {
+                    Some((c, _)) => *c += sign * coef,
+                    None => self.terms.push((sign * coef, term)),
+                }
+            }
+        }
+    }
+
+    fn build(mut self, branch: Branch) -> ExprNode {
+        self.terms.retain(|(c, _)| *c != ZERO);
+        self.terms.sort_by(|(_, a), (_, b)| a.canonical_cmp(b));
+
+        let mut terms = self.terms.into_iter().map(|(coef, term)| {
+            let scaled = |coef: Complex64| {
+                Product {
+                    coef,
+                    ..Product::of(term, branch)
+                }
+                .build()
+            };
+            if is_negative(coef) {
+                (BinOp::Minus, scaled(-coef))
+            } else {
+                (BinOp::Plus, scaled(coef))
+            }
+        });
+
+        let first = match terms.next() {
+            Some((BinOp::Minus, term)) => ExprNode::UnaryOp(UnaryOp::Minus, Box::new(term)),
+            Some((_, term)) => term,
+            None => return number(self.constant),
+        };
+        let mut rest: Vec<_> = terms.collect();
+        // the real and imaginary parts are added on their own, keeping the sum flat
+        let parts = [
+            self.constant.re.into(),
+            Complex64::new(0.0, self.constant.im),
+        ];
+        for &v in parts.iter().filter(|v| **v != ZERO) {
+            if is_negative(v) {
+                rest.push((BinOp::Minus, number(-v)))
+            } else {
+                rest.push((BinOp::Plus, number(v)))
+            }
+        }
+        ExprNode::chain(first, rest)
+    }
+}
+
+impl Product {
+    fn of(node: ExprNode, branch: Branch) -> Self {
+        let mut product = Product {
+            coef: ONE,
+            factors: vec![],
+        };
+        product.collect(node, 1.0, branch);
+        product
+    }
+
+    fn collect(&mut self, node: ExprNode, exp: f64, branch: Branch) {
+        match node {
+            ExprNode::BinOp(a, BinOp::Mult, b) => {
+                self.collect(*a, exp, branch);
+                self.collect(*b, exp, branch);
+            }
+            ExprNode::BinOp(a, BinOp::Div, b) => {
+                self.collect(*a, exp, branch);
+                self.collect(*b, -exp, branch);
+            }
+            ExprNode::UnaryOp(UnaryOp::Plus, v) => self.collect(*v, exp, branch),
+            ExprNode::UnaryOp(UnaryOp::Minus, v) => {
+                self.coef = -self.coef;
+                self.collect(*v, exp, branch);
+            }
+            ExprNode::BinOp(base, BinOp::Pow, e) => match e.literal() {
+                Some(e) => self.push(*base, e * exp, branch),
+                None => self.push(bin_op(*base, BinOp::Pow, *e), exp, branch),
+            },
+            node => self.push(node, exp, branch),
+        }
+    }
+
+    fn push(&mut self, base: ExprNode, exp: f64, branch: Branch) {
+        if let Some(v) = base.constant_value(branch) {
+            if exp == 1.0 {
+                self.coef *= v;
+                return;
+            }
+            // `0` in a denominator is kept, so the division still fails
+            if exp == -1.0 && v != ZERO {
+                self.coef /= v;
+                return;
+            }
+        }
+        match self.factors.iter_mut().find(|(b, _)| *b == base) {
+            Some((_, e)) => *e += exp,
+            None => self.factors.push((base, exp)),
+        }
+    }
+
+    fn build(mut self) -> ExprNode {
+        if self.coef == ZERO {
+            return ExprNode::Number(0.0);
+        }
+        self.factors.retain(|(_, e)| *e != 0.0);
+        self.factors.sort_by(|(a, _), (b, _)| a.canonical_cmp(b));
+
+        let power = |base: ExprNode, exp: f64| {
+            if exp == 1.0 {
+                base
+            } else {
+                bin_op(base, BinOp::Pow, number(exp.into()))
+            }
+        };
+        // one chain, so products stay flat like `2 * x * y`
+        let chain = |factors: Vec<ExprNode>| {
+            let mut factors = factors.into_iter();
+            let first = factors.next()?;
+            Some(ExprNode::chain(
+                first,
+                factors.map(|f| (BinOp::Mult, f)).collect(),
+            ))
+        };
+
+        let negative = is_negative(self.coef);
+        let coef = if negative { -self.coef } else { self.coef };
+        let (num, den): (Vec<_>, Vec<_>) = self.factors.into_iter().partition(|(_, e)| *e > 0.0);
+        let mut num: Vec<_> = num.into_iter().map(|(b, e)| power(b, e)).collect();
+        if coef != ONE || num.is_empty() {
+            num.insert(0, number(coef));
+        }
+        let den = chain(den.into_iter().map(|(b, e)| power(b, -e)).collect());
+
+        let mut out = chain(num).unwrap();
+        if let Some(den) = den {
+            out = bin_op(out, BinOp::Div, den)
+        }
+        if negative {
+            out = ExprNode::UnaryOp(UnaryOp::Minus, Box::new(out))
+        }
+        out
+    }
+}
+
+impl ExprNode {
+    pub fn is_constant(&self) -> bool {
+        match self {
+            ExprNode::Var(_) => false,
+            ExprNode::Number(_)
+            | ExprNode::Decimal(..)
+            | ExprNode::E
+            | ExprNode::Pi
+            | ExprNode::I
+            | ExprNode::Unit(_) => true,
+            ExprNode::BinOp(a, _, b) => a.is_constant() && b.is_constant(),
+            ExprNode::UnaryOp(_, v) | ExprNode::Abs(v) | ExprNode::Func(_, v) => v.is_constant(),
+        }
+    }
+
+    /// The value of the expression if it is a finite constant
+    fn constant_value(&self, branch: Branch) -> Option<Complex64> {
+        if !self.is_constant() {
+            return None;
+        }
+        let v: Complex64 = self.run(&[], branch);
+        v.is_finite().then_some(v)
+    }
+
+    /// The value of a number, which may be negated like the negative numbers `simplify` builds
+    fn literal(&self) -> Option<f64> {
+        match self {
+            ExprNode::Number(v) => Some(*v),
+            ExprNode::UnaryOp(UnaryOp::Plus, v) => v.literal(),
+            ExprNode::UnaryOp(UnaryOp::Minus, v) => v.literal().map(|v| -v),
+            _ => None,
+        }
+    }
+
+    /// Folds constants, removes identities such as `x*1`, `x+0` and `--x`, and merges like terms
+    /// into a canonical form of sums and products
+    pub fn simplify(&self, branch: Branch) -> ExprNode {
+        let node = match self {
+            ExprNode::BinOp(a, op, b) => bin_op(a.simplify(branch), *op, b.simplify(branch)),
+            ExprNode::UnaryOp(op, v) => ExprNode::UnaryOp(*op, Box::new(v.simplify(branch))),
+            ExprNode::Abs(v) => ExprNode::Abs(Box::new(v.simplify(branch))),
+            ExprNode::Func(f, v) => ExprNode::Func(*f, Box::new(v.simplify(branch))),
+            // constants are folded in `f64` anyway
+            ExprNode::Decimal(v, _) => return ExprNode::Number(*v),
+            leaf => return leaf.clone(),
+        };
+
+        if let Some(v) = node.constant_value(branch) {
+            return number(v);
+        }
+
+        match node {
+            ExprNode::BinOp(_, BinOp::Plus | BinOp::Minus, _) | ExprNode::UnaryOp(..) => {
+                Sum::of(node, branch).build(branch)
+            }
+            ExprNode::BinOp(_, BinOp::Mult | BinOp::Div, _) => Product::of(node, branch).build(),
+            ExprNode::BinOp(_, BinOp::Pow, ref exp) if exp.literal().is_some() => {
+                Product::of(node, branch).build()
+            }
+            ExprNode::BinOp(ref base, BinOp::Pow, _) if **base == ExprNode::Number(1.0) => {
+                ExprNode::Number(1.0)
+            }
+            node => node,
+        }
+    }
+
+    fn canonical_rank(&self) -> u8 {
+        match self {
+            ExprNode::Number(_) | ExprNode::Decimal(..) => 0,
+            ExprNode::E => 1,
+            ExprNode::Pi => 2,
+            ExprNode::I => 3,
+            ExprNode::Var(_) => 4,
+            ExprNode::BinOp(..) => 5,
+            ExprNode::UnaryOp(..) => 6,
+            ExprNode::Abs(_) => 7,
+            ExprNode::Func(..) => 8,
+            ExprNode::Unit(_) => 9,
+        }
+    }
+
+    /// Total order used to sort the terms of sums and factors of products
+    pub fn canonical_cmp(&self, other: &ExprNode) -> Ordering {
+        match (self, other) {
+            (ExprNode::Number(a), ExprNode::Number(b)) => a.total_cmp(b),
+            (ExprNode::Var(a), ExprNode::Var(b)) => a.cmp(b),
+            (ExprNode::BinOp(a1, op1, b1), ExprNode::BinOp(a2, op2, b2)) => (*op1 as u8)
+                .cmp(&(*op2 as u8))
+                .then_with(|| a1.canonical_cmp(a2))
+                .then_with(|| b1.canonical_cmp(b2)),
+            (ExprNode::UnaryOp(op1, a), ExprNode::UnaryOp(op2, b)) => (*op1 as u8)
+                .cmp(&(*op2 as u8))
+                .then_with(|| a.canonical_cmp(b)),
+            (ExprNode::Abs(a), ExprNode::Abs(b)) => a.canonical_cmp(b),
+            (ExprNode::Func(f1, a), ExprNode::Func(f2, b)) => (*f1 as u8)
+                .cmp(&(*f2 as u8))
+                .then_with(|| a.canonical_cmp(b)),
+            (a, b) => a.canonical_rank().cmp(&b.canonical_rank()),
+        }
+    }
+}
+
+impl System {
+    /// Simplifies both sides of every equation
+    pub fn simplify(&mut self) {
+        for eq in &mut self.eqs {
+            eq.left = eq.left.simplify(self.branch);
+            eq.right = eq.right.simplify(self.branch);
+        }
+    }
+}
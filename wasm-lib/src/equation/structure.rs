@@ -0,0 +1,347 @@
+use num_complex::Complex64;
+
+use crate::parsing::ast::ExprNode;
+
+use super::system::{NewtonStats, System};
+
+/// Equations solved together for a set of variables
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub eqs: Vec<usize>,
+    pub vars: Vec<usize>,
+}
+
+/// Maximum matching between equations and the variables they contain
+#[derive(Debug)]
+pub struct Matching {
+    pub eq_var: Vec<Option<usize>>,
+    pub var_eq: Vec<Option<usize>>,
+}
+
+impl ExprNode {
+    pub fn collect_vars(&self, out: &mut Vec<usize>) {
+        match self {
+            ExprNode::Var(id) => {
+                if !out.contains(&(*id as usize)) {
+                    out.push(*id as usize)
+                }
+            }
+            ExprNode::Number(_)
+            | ExprNode::Decimal(..)
+            | ExprNode::E
+            | ExprNode::Pi
+            | ExprNode::I
+            | ExprNode::Unit(_) => {}
+            ExprNode::BinOp(a, _, b) => {
+                a.collect_vars(out);
+                b.collect_vars(out);
+            }
+            ExprNode::UnaryOp(_, v) | ExprNode::Abs(v) | ExprNode::Func(_, v) => {
+                v.collect_vars(out)
+            }
+        }
+    }
+}
+
+impl Matching {
+    /// Tries to match `root` to a variable, rematching other equations along an augmenting
+    /// path. `visited_by` holds the equation whose search last went through each variable.
+    fn augment(
+        &mut self,
+        incidence: &[Vec<usize>],
+        root: usize,
+        visited_by: &mut [Option<usize>],
+    ) -> bool {
+        // equations of the path with the position of the next variable each tries, and the
+        // variable each one but the last is trying to take
+        let mut path = vec![(root, 0)];
+        let mut taking = vec![];
+        while let Some(&(eq, next)) = path.last() {
+            let Some(&var) = incidence[eq].get(next) else {
+                path.pop();
+                taking.pop();
+                continue;
+            };
+            path.last_mut().unwrap().1 += 1;
+            if visited_by[var] == Some(root) {
+                continue;
+            }
+            visited_by[var] = Some(root);
+            taking.push(var);
+            match self.var_eq[var] {
+                Some(other) => path.push((other, 0)),
+                None => {
+                    for (&(eq, _), &var) in path.iter().zip(&taking) {
+                        self.var_eq[var] = Some(eq);
+                        self.eq_var[eq] = Some(var);
+                    }
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn is_perfect(&self) -> bool {
+        self.eq_var.iter().chain(&self.var_eq).all(Option::is_some)
+    }
+}
+
+/// Structural problems found before solving
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    CountMismatch {
+        eqs: usize,
+        vars: usize,
+    },
+    /// Variable that appears in no equation
+    UnusedVar(usize),
+    /// Equations constraining their variables more times than there are variables, so
+    /// `surplus` of them are redundant or contradict the others
+    Overdetermined {
+        eqs: Vec<usize>,
+        surplus: usize,
+    },
+    /// Variables that the equations do not determine, `surplus` of them being free
+    Underdetermined {
+        vars: Vec<usize>,
+        surplus: usize,
+    },
+}
+
+/// Tarjan's algorithm over equations, where an equation depends on the equations matched to
+/// its variables
+struct Tarjan<'a> {
+    incidence: &'a [Vec<usize>],
+    matching: &'a Matching,
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    next_index: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn open(&mut self, eq: usize) {
+        self.index[eq] = Some(self.next_index);
+        self.low[eq] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(eq);
+        self.on_stack[eq] = true;
+    }
+
+    fn visit(&mut self, root: usize) {
+        // equations being visited, with the position of the next variable each follows
+        let mut calls = vec![(root, 0)];
+        self.open(root);
+        while let Some(&(eq, next)) = calls.last() {
+            if let Some(&var) = self.incidence[eq].get(next) {
+                calls.last_mut().unwrap().1 += 1;
+                let Some(dep) = self.matching.var_eq[var] else {
+                    continue;
+                };
+                match self.index[dep] {
+                    None => {
+                        self.open(dep);
+                        calls.push((dep, 0));
+                    }
+                    Some(index) if self.on_stack[dep] => self.low[eq] = self.low[eq].min(index),
+                    Some(_) => (),
+                }
+                continue;
+            }
+            calls.pop();
+            if let Some(&(caller, _)) = calls.last() {
+                self.low[caller] = self.low[caller].min(self.low[eq]);
+            }
+            self.close(eq);
+        }
+    }
+
+    /// Pops the component of `eq` once every equation it depends on is visited
+    fn close(&mut self, eq: usize) {
+        if Some(self.low[eq]) == self.index[eq] {
+            let mut component = vec![];
+            loop {
+                let top = self.stack.pop().unwrap();
+                self.on_stack[top] = false;
+                component.push(top);
+                if top == eq {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+impl System {
+    pub fn whole_block(&self) -> Block {
+        Block {
+            eqs: (0..self.eqs.len()).collect(),
+            vars: (0..self.var_amount).collect(),
+        }
+    }
+
+    /// The variables each equation contains
+    pub fn incidence(&self) -> Vec<Vec<usize>> {
+        self.eqs
+            .iter()
+            .map(|eq| {
+                let mut vars = vec![];
+                eq.left.collect_vars(&mut vars);
+                eq.right.collect_vars(&mut vars);
+                vars
+            })
+            .collect()
+    }
+
+    pub fn matching(&self, incidence: &[Vec<usize>]) -> Matching {
+        let mut matching = Matching {
+            eq_var: vec![None; self.eqs.len()],
+            var_eq: vec![None; self.var_amount],
+        };
+        let mut visited_by = vec![None; self.var_amount];
+        for eq in 0..self.eqs.len() {
+            matching.augment(incidence, eq, &mut visited_by);
+        }
+        matching
+    }
+
+    /// Finds over and under-constrained parts of the system from the Dulmage–Mendelsohn
+    /// decomposition of its maximum matching
+    pub fn analyze(&self) -> Vec<Diagnostic> {
+        let incidence = self.incidence();
+        let matching = self.matching(&incidence);
+        let mut out = vec![];
+
+        if self.eqs.len() != self.var_amount {
+            out.push(Diagnostic::CountMismatch {
+                eqs: self.eqs.len(),
+                vars: self.var_amount,
+            })
+        }
+
+        let mut var_incidence = vec![vec![]; self.var_amount];
+        for (eq, vars) in incidence.iter().enumerate() {
+            for &var in vars {
+                var_incidence[var].push(eq)
+            }
+        }
+        for (var, eqs) in var_incidence.iter().enumerate() {
+            if eqs.is_empty() {
+                out.push(Diagnostic::UnusedVar(var))
+            }
+        }
+
+        // equations reachable from unmatched ones by alternating paths could each be the
+        // redundant one, and the same goes for variables
+        let reachable = |starts: Vec<usize>, edges: &[Vec<usize>], matched: &[Option<usize>]| {
+            let mut seen = vec![false; edges.len()];
+            let mut stack = starts;
+            while let Some(node) = stack.pop() {
+                if std::mem::replace(&mut seen[node], true) {
+                    continue;
+                }
+                stack.extend(edges[node].iter().filter_map(|&other| matched[other]));
+            }
+            (0..edges.len()).filter(|&i| seen[i]).collect::<Vec<_>>()
+        };
+
+        let free_eqs: Vec<usize> = (0..self.eqs.len())
+            .filter(|&eq| matching.eq_var[eq].is_none())
+            .collect();
+        if !free_eqs.is_empty() {
+            out.push(Diagnostic::Overdetermined {
+                surplus: free_eqs.len(),
+                eqs: reachable(free_eqs, &incidence, &matching.var_eq),
+            })
+        }
+
+        let free_vars: Vec<usize> = (0..self.var_amount)
+            .filter(|&var| matching.var_eq[var].is_none() && !var_incidence[var].is_empty())
+            .collect();
+        if !free_vars.is_empty() {
+            out.push(Diagnostic::Underdetermined {
+                surplus: free_vars.len(),
+                vars: reachable(free_vars, &var_incidence, &matching.eq_var),
+            })
+        }
+
+        out
+    }
+
+    /// Splits the system into blocks that can be solved one after the other, each only
+    /// depending on the variables of earlier blocks, or `None` if it is not structurally square
+    pub fn blocks(&self) -> Option<Vec<Block>> {
+        let incidence = self.incidence();
+        let matching = self.matching(&incidence);
+        if !matching.is_perfect() {
+            return None;
+        }
+
+        let n = self.eqs.len();
+        let mut tarjan = Tarjan {
+            incidence: &incidence,
+            matching: &matching,
+            index: vec![None; n],
+            low: vec![0; n],
+            stack: vec![],
+            on_stack: vec![false; n],
+            next_index: 0,
+            components: vec![],
+        };
+        for eq in 0..n {
+            if tarjan.index[eq].is_none() {
+                tarjan.visit(eq)
+            }
+        }
+
+        // components come out with their dependencies first
+        Some(
+            tarjan
+                .components
+                .into_iter()
+                .map(|mut eqs| {
+                    eqs.sort_unstable();
+                    let vars = eqs.iter().map(|&eq| matching.eq_var[eq].unwrap()).collect();
+                    Block { eqs, vars }
+                })
+                .collect(),
+        )
+    }
+
+    /// Solves the blocks of the system in order, falling back to solving the whole system at
+    /// once if it cannot be split
+    pub fn solve_blocks<R: Iterator<Item = Complex64>>(
+        &self,
+        iter: usize,
+        rand: R,
+    ) -> Option<Vec<Complex64>> {
+        self.converge_blocks(iter, rand, &mut NewtonStats::default())
+    }
+
+    /// `solve_blocks`, adding what the iterations did to `stats`
+    pub fn converge_blocks<R: Iterator<Item = Complex64>>(
+        &self,
+        iter: usize,
+        rand: R,
+        stats: &mut NewtonStats,
+    ) -> Option<Vec<Complex64>> {
+        let Some(blocks) = self.blocks() else {
+            return self.converge(iter, rand, stats);
+        };
+        let mut solution: Vec<Complex64> = rand.take(self.var_amount).collect();
+        if solution.len() < self.var_amount {
+            return None;
+        }
+
+        for block in &blocks {
+            if !self.converge_block(&mut solution, block, iter, stats) {
+                return None;
+            }
+        }
+        Some(solution)
+    }
+}
@@ -1,74 +1,419 @@
-use std::{collections::HashMap, time::Instant};
+use std::collections::HashMap;
 
 use nalgebra::{DMatrix, DVector};
 use num_complex::Complex64;
+use serde::Deserialize;
 
-use crate::{log, parsing::ast::ExprNode};
+use crate::parsing::{ast::ExprNode, operators::BinOp, symbols::SymbolTable};
 
-#[derive(Debug)]
+use super::{double_double::ComplexDD, running::Branch, scalar::Scalar, structure::Block};
+
+/// How Newton iterations get the Jacobian
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    /// Finite differences at every step, costing `var_amount + 1` evaluations each
+    #[default]
+    Newton,
+    /// Broyden's good method, correcting the Jacobian by the change of the residuals along
+    /// each step and only taking a fresh one when the residuals stall
+    GoodBroyden,
+    /// Broyden's bad method, which corrects the inverse Jacobian instead
+    BadBroyden,
+}
+
+#[derive(Debug, Clone)]
 pub struct System {
     pub eqs: Vec<Equation>,
     pub var_amount: usize,
+    /// Name and metadata of every variable, by id
+    pub symbols: SymbolTable,
+    pub branch: Branch,
+    /// Largest residual of a solution
+    pub tolerance: f64,
+    pub method: Method,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Equation {
     pub left: ExprNode,
     pub right: ExprNode,
 }
+
+/// What Newton iterations did while solving
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NewtonStats {
+    /// Jacobians computed, rather than updated by Broyden's methods
+    pub jacobians: usize,
+    /// Whether a root found looked multiple, with steps that shrank only linearly or a
+    /// singular Jacobian
+    pub multiple: bool,
+}
+
 const DELTA: f64 = 0.000001;
+pub const TOLERANCE: f64 = 0.00000001;
+/// Newton steps shrinking by a steady factor above this near a root mean a multiple root
+const LINEAR_RATIO: f64 = 0.3;
+/// Largest change of that factor between steps, relative to it, that counts as steady
+const STEADY_RATIO: f64 = 0.05;
+/// Multiple roots are only looked for once the residuals are below this
+const NEAR_ROOT: f64 = 1e-2;
+/// Broyden's methods take a fresh Jacobian once a step shrinks the residuals by less than this
+const BROYDEN_DECREASE: f64 = 0.5;
+/// Step of the double-double Jacobian, relative to the size of the values
+const PRECISE_DELTA: f64 = 1e-12;
+/// Most steps taken to polish a solution in double-double precision
+const REFINE_ITERATIONS: usize = 50;
+/// Steps smaller than this, relative to the values, are below double-double precision
+const REFINE_TOLERANCE: f64 = 1e-32;
+/// Rounding error of evaluating an equation, relative to the size of its sides. Residuals this
+/// small are as good as zero, even when they are above the tolerance.
+const ROUNDING: f64 = 1e-13;
+
+impl Method {
+    /// `inverse` corrected by rank one so it maps the change `df` of the residuals to the step
+    /// `dx` that caused it, or `None` for a fresh Jacobian
+    fn update(
+        self,
+        inverse: DMatrix<Complex64>,
+        dx: &DVector<Complex64>,
+        df: &DVector<Complex64>,
+    ) -> Option<DMatrix<Complex64>> {
+        let h_df = &inverse * df;
+        let (row, den) = match self {
+            Method::Newton => return None,
+            // the inverse of the Jacobian changed only along `dx`, by Sherman-Morrison
+            Method::GoodBroyden => (dx.adjoint() * &inverse, dx.dotc(&h_df)),
+            Method::BadBroyden => (df.adjoint(), df.dotc(df)),
+        };
+        if den.norm() == 0.0 || !den.is_finite() {
+            return None;
+        }
+        Some(inverse + (dx - h_df) * row / den)
+    }
+}
+
+impl ExprNode {
+    fn constant(v: Complex64) -> ExprNode {
+        let re = ExprNode::Number(v.re);
+        if v.im == 0.0 {
+            return re;
+        }
+        let im = ExprNode::BinOp(
+            Box::new(ExprNode::Number(v.im)),
+            BinOp::Mult,
+            Box::new(ExprNode::I),
+        );
+        ExprNode::BinOp(Box::new(re), BinOp::Plus, Box::new(im))
+    }
+
+    /// Replaces every variable `id` by `values[id]`
+    pub fn substitute(&self, values: &[ExprNode]) -> ExprNode {
+        match self {
+            ExprNode::Var(id) => values[*id as usize].clone(),
+            ExprNode::BinOp(a, op, b) => ExprNode::BinOp(
+                Box::new(a.substitute(values)),
+                *op,
+                Box::new(b.substitute(values)),
+            ),
+            ExprNode::UnaryOp(op, v) => ExprNode::UnaryOp(*op, Box::new(v.substitute(values))),
+            ExprNode::Abs(v) => ExprNode::Abs(Box::new(v.substitute(values))),
+            ExprNode::Func(f, v) => ExprNode::Func(*f, Box::new(v.substitute(values))),
+            other => other.clone(),
+        }
+    }
+}
 
 impl System {
-    pub fn jacobian(&self, args: &[Complex64]) -> DMatrix<Complex64> {
-        let mut shifted_args = args.iter().map(|_| args.to_vec()).collect::<Vec<_>>();
+    /// The system with the variables that have a value in `params` replaced by it, leaving the
+    /// others as its unknowns in the same order
+    pub fn bind(&self, params: &[Option<Complex64>]) -> System {
+        let value = |id: usize| params.get(id).copied().flatten();
+        let keep: Vec<bool> = (0..self.var_amount).map(|id| value(id).is_none()).collect();
+        let (symbols, ids) = self.symbols.subset(&keep);
+        let values: Vec<ExprNode> = ids
+            .iter()
+            .enumerate()
+            .map(|(id, new_id)| match new_id {
+                Some(new_id) => ExprNode::Var(*new_id),
+                None => ExprNode::constant(value(id).unwrap_or_default()),
+            })
+            .collect();
 
-        #[allow(clippy::needless_range_loop)]
-        for i in 0..self.var_amount {
-            shifted_args[i][i] += Complex64::new(DELTA, DELTA);
+        System {
+            eqs: self
+                .eqs
+                .iter()
+                .map(|eq| Equation {
+                    left: eq.left.substitute(&values),
+                    right: eq.right.substitute(&values),
+                })
+                .collect(),
+            var_amount: symbols.len(),
+            symbols,
+            branch: self.branch,
+            tolerance: self.tolerance,
+            method: self.method,
         }
+    }
+
+    /// Binds the variables named in `params`, ignoring names that are not in the system
+    pub fn with_params(&self, params: &HashMap<String, Complex64>) -> System {
+        let values: Vec<Option<Complex64>> = self
+            .symbols
+            .iter()
+            .map(|s| params.get(&s.name).copied())
+            .collect();
+        self.bind(&values)
+    }
+
+    /// Every equation the way it was parsed, as source and as LaTeX
+    pub fn preview(&self) -> Vec<(String, String)> {
+        let symbols = &self.symbols;
+        self.eqs
+            .iter()
+            .map(|eq| {
+                (
+                    format!(
+                        "{} = {}",
+                        eq.left.display(symbols),
+                        eq.right.display(symbols)
+                    ),
+                    format!("{} = {}", eq.left.latex(symbols), eq.right.latex(symbols)),
+                )
+            })
+            .collect()
+    }
+
+    /// Id of the variable called `name`
+    pub fn var_id(&self, name: &str) -> Option<usize> {
+        self.symbols.id(name).map(|id| id as usize)
+    }
+
+    pub fn jacobian(&self, args: &[Complex64]) -> DMatrix<Complex64> {
+        self.block_jacobian(args, &self.whole_block())
+    }
+    /// Jacobian of the equations of `block` with respect to its variables
+    pub fn block_jacobian(&self, args: &[Complex64], block: &Block) -> DMatrix<Complex64> {
+        let base = self.block_run(args, block);
+        let mut shifted_args = args.to_vec();
+        // differences are divided by the whole complex shift, not just its real part, or
+        // every derivative would be off by a factor of `1 + i`
+        let shift = Complex64::new(DELTA, DELTA);
 
         DMatrix::from_iterator(
-            self.eqs.len(),
-            self.var_amount,
-            (0..self.var_amount).flat_map(|i| {
-                let shifted_args: &[Complex64] = &shifted_args[i];
-                self.eqs
+            block.eqs.len(),
+            block.vars.len(),
+            block.vars.iter().flat_map(|&var| {
+                shifted_args[var] += shift;
+                let column = self.block_run(&shifted_args, block);
+                shifted_args[var] = args[var];
+                (column - &base)
                     .iter()
-                    .map(move |eq| (eq.calc(shifted_args) - eq.calc(args)) / DELTA)
+                    .map(|v| v / shift)
+                    .collect::<Vec<_>>()
             }),
         )
     }
-    pub fn run(&self, args: &[Complex64]) -> DVector<Complex64> {
-        DVector::from_iterator(self.eqs.len(), self.eqs.iter().map(|eq| eq.calc(args)))
+    pub fn run<T: Scalar>(&self, args: &[T]) -> DVector<T> {
+        self.block_run(args, &self.whole_block())
+    }
+    pub fn block_run<T: Scalar>(&self, args: &[T], block: &Block) -> DVector<T> {
+        DVector::from_iterator(
+            block.eqs.len(),
+            block
+                .eqs
+                .iter()
+                .map(|&eq| self.eqs[eq].calc(args, self.branch)),
+        )
     }
     pub fn solve<R: Iterator<Item = Complex64>>(
         &self,
         iter: usize,
         rand: R,
     ) -> Option<Vec<Complex64>> {
-        let mut solution: DVector<Complex64> = DVector::from_iterator(self.var_amount, rand);
+        self.converge(iter, rand, &mut NewtonStats::default())
+    }
+    /// `solve`, adding what the iterations did to `stats`
+    pub fn converge<R: Iterator<Item = Complex64>>(
+        &self,
+        iter: usize,
+        rand: R,
+        stats: &mut NewtonStats,
+    ) -> Option<Vec<Complex64>> {
+        let mut solution: Vec<Complex64> = rand.take(self.var_amount).collect();
+        if solution.len() < self.var_amount {
+            return None;
+        }
 
+        self.converge_block(&mut solution, &self.whole_block(), iter, stats)
+            .then_some(solution)
+    }
+    /// Newton iterations on the variables of `block`, keeping every other variable fixed, with
+    /// the Jacobian of `self.method`. Steps are scaled by the multiplicity once they shrink
+    /// linearly, as they do at multiple roots.
+    pub fn solve_block(&self, args: &mut [Complex64], block: &Block, iter: usize) -> bool {
+        self.converge_block(args, block, iter, &mut NewtonStats::default())
+    }
+    /// `solve_block`, adding what the iterations did to `stats`
+    pub fn converge_block(
+        &self,
+        args: &mut [Complex64],
+        block: &Block,
+        iter: usize,
+        stats: &mut NewtonStats,
+    ) -> bool {
+        let mut residual = self.block_run(args, block);
+        let mut multiplicity = 1.0;
+        // whether steps ever shrank linearly, even if scaling them did not work out
+        let mut multiple = false;
+        let (mut last_size, mut last_ratio) = (f64::INFINITY, 0.0);
+        // pseudo-inverse of the Jacobian, kept between steps by Broyden's methods
+        let mut kept: Option<DMatrix<Complex64>> = None;
         for _ in 0..iter {
-            let arg_array: Vec<Complex64> = solution.iter().copied().collect();
+            let inverse = match kept.take() {
+                Some(inverse) => inverse,
+                None => {
+                    // finite differences are off by about their step, which is more than the
+                    // whole Jacobian close to a multiple root
+                    stats.jacobians += 1;
+                    let j = if multiplicity > 1.0 {
+                        self.exact_block_jacobian(args, block)
+                    } else {
+                        self.block_jacobian(args, block)
+                    };
+                    let j_t = j.transpose();
+                    let Some(inverse) = (j_t.clone() * j).try_inverse() else {
+                        // the Jacobian is singular at multiple roots, which may be hit exactly
+                        let converged = self.within_tolerance(args, block, &residual);
+                        stats.multiple |= converged;
+                        return converged;
+                    };
+                    inverse * j_t
+                }
+            };
 
-            let j = self.jacobian(&arg_array);
-            let j_t = j.transpose();
-            let j_i = (j_t.clone() * j).try_inverse()? * j_t;
+            let step = &inverse * &residual;
+            let ratio = step.norm() / last_size;
+            let steady = (ratio - last_ratio).abs() < STEADY_RATIO * ratio;
+            if multiplicity == 1.0
+                && residual.norm() < NEAR_ROOT
+                && (LINEAR_RATIO..1.0).contains(&ratio)
+                && steady
+            {
+                multiplicity = (1.0 / (1.0 - ratio)).round();
+                multiple |= multiplicity > 1.0;
+            }
+            (last_size, last_ratio) = (step.norm(), ratio);
 
-            solution -= j_i * self.run(&arg_array);
+            let before: Vec<Complex64> = block.vars.iter().map(|&var| args[var]).collect();
+            for (&var, step) in block.vars.iter().zip(step.iter()) {
+                args[var] -= step * multiplicity;
+            }
+            let mut next = self.block_run(args, block);
+            let worse = next.norm().is_nan() || next.norm() >= residual.norm();
+            if multiplicity > 1.0 && worse {
+                // not a multiple root after all, so take the plain step instead
+                multiplicity = 1.0;
+                for ((&var, step), v) in block.vars.iter().zip(step.iter()).zip(&before) {
+                    args[var] = v - step;
+                }
+                next = self.block_run(args, block);
+            }
+            if self.within_tolerance(args, block, &next) {
+                stats.multiple |= multiple;
+                return true;
+            }
 
-            if let Some(sol) = self.verify(&solution) {
-                return Some(sol);
+            if multiplicity == 1.0 && next.norm() < BROYDEN_DECREASE * residual.norm() {
+                let moved = block
+                    .vars
+                    .iter()
+                    .zip(&before)
+                    .map(|(&var, v)| args[var] - v);
+                let moved = DVector::from_iterator(block.vars.len(), moved);
+                kept = self.method.update(inverse, &moved, &(&next - &residual));
             }
+            residual = next;
         }
-        None
+        false
+    }
+    fn within_tolerance(
+        &self,
+        args: &[Complex64],
+        block: &Block,
+        residual: &DVector<Complex64>,
+    ) -> bool {
+        block.eqs.iter().zip(residual.iter()).all(|(&eq, v)| {
+            v.norm() < self.tolerance || {
+                let (left, right) = self.eqs[eq].sides(args, self.branch);
+                v.norm() < ROUNDING * (left.norm() + right.norm())
+            }
+        })
     }
     pub fn verify(&self, solution: &DVector<Complex64>) -> Option<Vec<Complex64>> {
         let arg_array: Vec<Complex64> = solution.iter().copied().collect();
-        if self.run(&arg_array).iter().all(|v| v.norm() < 0.00000001) {
+        if self.within_tolerance(&arg_array, &self.whole_block(), &self.run(&arg_array)) {
             Some(arg_array)
         } else {
             None
         }
     }
+
+    /// Jacobian from double-double evaluations, with steps relative to the size of the values
+    /// so it stays accurate where `f64` steps would be lost in rounding
+    fn precise_jacobian(&self, args: &[ComplexDD]) -> DMatrix<Complex64> {
+        let base = self.run(args);
+        let mut shifted_args = args.to_vec();
+        DMatrix::from_iterator(
+            self.eqs.len(),
+            self.var_amount,
+            (0..self.var_amount).flat_map(|var| {
+                let size = args[var].to_complex().norm().max(1.0);
+                let shift = Complex64::new(PRECISE_DELTA * size, PRECISE_DELTA * size);
+                shifted_args[var] = args[var] + shift.into();
+                let column = self.run(&shifted_args);
+                shifted_args[var] = args[var];
+                column
+                    .iter()
+                    .zip(base.iter())
+                    .map(|(a, b)| (*a - *b).to_complex() / shift)
+                    .collect::<Vec<_>>()
+            }),
+        )
+    }
+
+    /// Polishes a solution to about 32 significant digits by Newton steps with the residuals
+    /// evaluated in double-double precision. The Jacobian at `solution` is reused for every
+    /// step, each of which gains about as many digits as it has. Returns `None` if it is
+    /// singular there.
+    pub fn refine(&self, solution: &[Complex64]) -> Option<Vec<ComplexDD>> {
+        let mut values: Vec<ComplexDD> = solution.iter().map(|&v| v.into()).collect();
+        let j = self.precise_jacobian(&values);
+        let j_t = j.transpose();
+        let pseudo_inverse = (j_t.clone() * j).try_inverse()? * j_t;
+
+        let mut last_size = f64::INFINITY;
+        for _ in 0..REFINE_ITERATIONS {
+            let residual = self.run(&values).map(|v| v.to_complex());
+            let step = &pseudo_inverse * residual;
+            let size = step
+                .iter()
+                .zip(&values)
+                .map(|(s, v)| s.norm() / v.to_complex().norm().max(f64::MIN_POSITIVE))
+                .fold(0.0, f64::max);
+            // once the steps stop shrinking they are rounding noise
+            if size.is_nan() || size >= last_size {
+                break;
+            }
+            for (v, s) in values.iter_mut().zip(step.iter()) {
+                *v = *v - ComplexDD::from(*s);
+            }
+            if size < REFINE_TOLERANCE {
+                break;
+            }
+            last_size = size;
+        }
+        Some(values)
+    }
 }
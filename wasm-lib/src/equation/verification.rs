@@ -0,0 +1,163 @@
+use nalgebra::DVector;
+use num_complex::Complex64;
+
+use super::{
+    double_double::{ComplexDD, DEFAULT_DIGITS},
+    system::System,
+};
+
+/// Jacobians with a condition number above this are singular to working precision
+const SINGULAR_CONDITION: f64 = 1e12;
+/// Largest distance probed from a root to tell its multiplicity, relative to the size of the
+/// values
+const PROBE: f64 = 1e-4;
+/// Smallest distance probed, relative to the size of the values, below which rounding hides
+/// how the Newton steps change
+const MIN_PROBE: f64 = 1e-7;
+/// Distance probed relative to the distance to the next root, so that a cluster of simple
+/// roots is not taken for one multiple root
+const SEPARATION_RATIO: f64 = 0.1;
+/// Most multiplicity told, beyond which the root is not isolated
+const MAX_MULTIPLICITY: f64 = 64.0;
+/// Relative rounding error of `ComplexDD` values
+const PRECISE_EPSILON: f64 = f64::EPSILON * f64::EPSILON;
+
+/// How far a solution can be trusted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verification {
+    /// Norm of the residuals of all equations
+    pub residual: f64,
+    /// Condition number of the Jacobian, taking its norm to be at least 1 like
+    /// `System::condition`, and infinite if it is singular
+    pub condition: f64,
+    /// Estimated distance of every value from the true root
+    pub errors: Vec<f64>,
+    /// Whether the root looks multiple, where the Jacobian is singular and only about
+    /// `1/multiplicity` of the digits are right
+    pub multiple: bool,
+}
+
+impl Verification {
+    /// Significant digits of `value` that agree with the root, given its estimated `error`
+    pub fn digits(value: Complex64, error: f64) -> usize {
+        if error == 0.0 {
+            return f64::DIGITS as usize;
+        }
+        let digits = (value.norm() / error).log10().floor();
+        digits.clamp(0.0, DEFAULT_DIGITS as f64) as usize
+    }
+}
+
+impl System {
+    /// Newton step from `args` towards the root, by least squares where the system is not
+    /// square or the Jacobian is singular
+    fn newton_step(&self, args: &[Complex64]) -> DVector<Complex64> {
+        self.residual_step(args, &self.run(args))
+    }
+
+    /// Newton step from `args` that would remove `residual`
+    fn residual_step(
+        &self,
+        args: &[Complex64],
+        residual: &DVector<Complex64>,
+    ) -> DVector<Complex64> {
+        let svd = self.exact_jacobian(args).svd(true, true);
+        let eps = svd.singular_values.max() * f64::EPSILON;
+        svd.solve(residual, eps)
+            .unwrap_or_else(|_| DVector::zeros(args.len()))
+    }
+
+    /// Multiplicity of the root at `solution` along the direction where the Jacobian is most
+    /// singular, 1 for simple roots. Newton steps near a root of multiplicity `m` are `1/m` of
+    /// the distance to it, so they change by `1/m` of a move in that direction. The move stays
+    /// well within the distance to the next root, which may be close but apart.
+    pub fn multiplicity(&self, solution: &[Complex64]) -> usize {
+        if self.eqs.len() < self.var_amount {
+            return 1;
+        }
+        let svd = self.exact_jacobian(solution).svd(true, true);
+        let (Some(u), Some(v_t)) = (svd.u, svd.v_t) else {
+            return 1;
+        };
+        let min = svd.singular_values.imin();
+        let direction = v_t.row(min).adjoint();
+        let size = solution.iter().map(|v| v.norm()).fold(1.0, f64::max);
+        let moved = |distance: f64| -> Vec<Complex64> {
+            solution
+                .iter()
+                .zip(direction.iter())
+                .map(|(v, d)| v + d * distance)
+                .collect()
+        };
+
+        // the roots of the quadratic model of the residual along `direction`, projected
+        // where the Jacobian is most singular
+        let h = PROBE * size;
+        let project = |args: &[Complex64]| u.column(min).dotc(&self.run(args));
+        let value = project(solution);
+        let curvature = (project(&moved(h)) + project(&moved(-h)) - value * 2.0) / (h * h);
+        let slope = svd.singular_values[min];
+        let separation =
+            2.0 * (slope * slope - curvature * value * 2.0).sqrt().norm() / curvature.norm();
+        // without curvature the separation is infinite or NaN, and the widest probe is safe
+        let distance = (SEPARATION_RATIO * separation)
+            .min(PROBE * size)
+            .max(MIN_PROBE * size);
+
+        let probe = moved(distance);
+        let change = (self.newton_step(&probe) - self.newton_step(solution)).norm();
+        let multiplicity = (distance / change).round();
+        if multiplicity.is_nan() || multiplicity > MAX_MULTIPLICITY {
+            1
+        } else {
+            multiplicity.max(1.0) as usize
+        }
+    }
+
+    /// Reports how far `solution` can be trusted, from the residual, the conditioning of the
+    /// Jacobian and the multiplicity of the root, as `multiplicity` gives it
+    pub fn verification(&self, solution: &[Complex64], multiplicity: usize) -> Verification {
+        self.verification_of(solution, &self.run(solution), multiplicity, f64::EPSILON)
+    }
+
+    /// `verification` of a refined solution, with the residual computed in `ComplexDD` so that
+    /// errors below what `f64` holds show
+    pub fn precise_verification(&self, precise: &[ComplexDD], multiplicity: usize) -> Verification {
+        let solution: Vec<Complex64> = precise.iter().map(|v| v.to_complex()).collect();
+        let residual = self.run(precise).map(|v| v.to_complex());
+        self.verification_of(&solution, &residual, multiplicity, PRECISE_EPSILON)
+    }
+
+    /// `verification` from the `residual` at `solution`, whose values are rounded to
+    /// `epsilon` of their size
+    fn verification_of(
+        &self,
+        solution: &[Complex64],
+        residual: &DVector<Complex64>,
+        multiplicity: usize,
+        epsilon: f64,
+    ) -> Verification {
+        let residual_norm = residual.norm();
+        let singular_values = self.exact_jacobian(solution).singular_values();
+        let min = singular_values.min();
+        let condition = if min == 0.0 || self.eqs.len() < self.var_amount {
+            f64::INFINITY
+        } else {
+            singular_values.max().max(1.0) / min
+        };
+
+        // the Newton step is `1/m` of the distance to a root of multiplicity `m`
+        let step = self.residual_step(solution, residual);
+        let errors = solution
+            .iter()
+            .zip(&step)
+            .map(|(v, s)| multiplicity as f64 * s.norm() + epsilon * v.norm())
+            .collect();
+        Verification {
+            residual: residual_norm,
+            condition,
+            errors,
+            multiple: multiplicity > 1 || condition > SINGULAR_CONDITION,
+        }
+    }
+}
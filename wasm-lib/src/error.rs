@@ -0,0 +1,42 @@
+use std::fmt;
+
+/// Side of an equation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// A side of equation `eq` is not a valid expression
+    Parse { eq: usize, side: Side, msg: String },
+    /// A side of equation `eq` combines quantities with incompatible units
+    Units { eq: usize, side: Side, msg: String },
+    /// Arguments of the wrong shape or size
+    InvalidInput(String),
+    /// A name that is not a variable of the system
+    UnknownVariable(String),
+    /// Equation `eq` cannot be rearranged into a formula for the variable
+    NotIsolatable { eq: usize, msg: String },
+    /// The equations contradict each other
+    Inconsistent,
+    /// Every attempt failed to converge
+    NoSolution,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse { msg, .. }
+            | Error::Units { msg, .. }
+            | Error::NotIsolatable { msg, .. } => write!(f, "{}", msg),
+            Error::InvalidInput(msg) => write!(f, "{}", msg),
+            Error::UnknownVariable(name) => write!(f, "Unknown variable `{}`", name),
+            Error::Inconsistent => write!(f, "The equations have no solution"),
+            Error::NoSolution => write!(f, "No solutions found"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -1,105 +1,92 @@
 #![deny(unused_must_use)]
 
-mod equation;
+pub mod equation;
+pub mod error;
+pub mod parsing;
+pub mod solver;
 mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 use std::collections::HashMap;
 
-use equation::system::{Equation, System};
-use itertools::Itertools;
 use num_complex::Complex64;
-use parsing::{ast::ExprNode, parser::Parser};
-use wasm_bindgen::prelude::*;
+
+use equation::system::{Equation, TOLERANCE};
+use parsing::{parser::Parser, symbols::SymbolTable};
+
+pub use equation::{
+    running::Branch,
+    scalar::Scalar,
+    system::{Method, System},
+};
+pub use error::{Error, Side};
+pub use solver::{Solution, SolverOptions};
+pub use utils::set_panic_hook;
+#[cfg(feature = "wasm")]
+pub use wasm::*;
 
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-mod parsing;
-
-#[wasm_bindgen]
-extern "C" {
-    fn alert(s: &str);
-
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-}
-
-#[wasm_bindgen]
-pub fn solve(
-    eqs: JsValue,
-    iter: usize,
-    initial_values: &[f64],
-) -> Result<Option<Vec<JsValue>>, PositionedError> {
-    let example: Vec<(String, String)> = serde_wasm_bindgen::from_value(eqs).unwrap();
-    let (system, names) = get_eqs(&example)?;
-
-    let solution = system.solve(
-        iter,
-        initial_values
-            .iter()
-            .tuples()
-            .map(|(a, b)| Complex64::new(*a, *b)),
-    );
-    match solution {
-        Some(sol) => {
-            return Ok(Some(
-                names
-                    .iter()
-                    .zip(sol)
-                    .map(|(name, sol)| {
-                        serde_wasm_bindgen::to_value(&(name, (sol.re, sol.im))).unwrap()
-                    })
-                    .collect(),
-            ))
-        }
-        None => Ok(None),
-    }
-}
-
-#[wasm_bindgen]
-pub struct PositionedError {
-    msg: String,
-    pub eq: usize,
-    pub second: bool,
-}
-#[wasm_bindgen]
-impl PositionedError {
-    #[wasm_bindgen(getter = msg)]
-    pub fn msg(&self) -> String {
-        self.msg.clone()
-    }
-}
-
-pub fn get_eqs(eqs: &[(String, String)]) -> Result<(System, Vec<String>), PositionedError> {
+/// Parses every `(left, right)` pair into an equation, numbering variables in the order
+/// they first appear, and checks that the units agree
+pub fn parse_system<L: AsRef<str>, R: AsRef<str>>(eqs: &[(L, R)]) -> Result<System, Error> {
     let mut out = vec![];
-    let mut name_map = HashMap::new();
+    let mut symbols = SymbolTable::new();
     for (i, (a, b)) in eqs.iter().enumerate() {
-        let mut parser = Parser::new(a, &mut name_map);
-        let left = parser.parse().map_err(|s| PositionedError {
-            msg: s,
+        let mut parser = Parser::new(a.as_ref(), &mut symbols);
+        let left = parser.parse().map_err(|msg| Error::Parse {
             eq: i,
-            second: false,
+            side: Side::Left,
+            msg,
         })?;
-        let mut parser = Parser::new(b, &mut name_map);
-        let right = parser.parse().map_err(|s| PositionedError {
-            msg: s,
+        let mut parser = Parser::new(b.as_ref(), &mut symbols);
+        let right = parser.parse().map_err(|msg| Error::Parse {
             eq: i,
-            second: true,
+            side: Side::Right,
+            msg,
         })?;
         out.push(Equation { left, right })
     }
 
-    let mut name_vec = vec![String::new(); name_map.len()];
-    for (n, id) in name_map {
-        name_vec[id as usize] = n
-    }
+    let mut system = System {
+        eqs: out,
+        var_amount: symbols.len(),
+        symbols,
+        branch: Branch::default(),
+        tolerance: TOLERANCE,
+        method: Method::default(),
+    };
+    system.check_units()?;
+    Ok(system)
+}
 
-    Ok((
-        System {
-            eqs: out,
-            var_amount: name_vec.len(),
-        },
-        name_vec,
-    ))
+/// Computes a single expression, with the values of its variables given by name, after checking
+/// that its units agree
+pub fn evaluate(
+    expr: &str,
+    bindings: &HashMap<String, Complex64>,
+    branch: Branch,
+) -> Result<Complex64, Error> {
+    let mut symbols = SymbolTable::new();
+    let node = Parser::new(expr, &mut symbols)
+        .parse()
+        .map_err(|msg| Error::Parse {
+            eq: 0,
+            side: Side::Left,
+            msg,
+        })?;
+    node.check_units(symbols.len())?;
+    let args = symbols
+        .iter()
+        .map(|s| {
+            bindings
+                .get(&s.name)
+                .copied()
+                .ok_or_else(|| Error::InvalidInput(format!("No value given for `{}`", s.name)))
+        })
+        .collect::<Result<Vec<Complex64>, Error>>()?;
+    Ok(node.run(&args, branch))
 }
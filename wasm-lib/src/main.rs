@@ -0,0 +1,371 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{Parser, ValueEnum};
+use num_complex::Complex64;
+use wasm_lib::{
+    equation::{
+        double_double::{ComplexDD, DEFAULT_DIGITS},
+        verification::Verification,
+    },
+    parse_system, Error, Method, Side, Solution, SolverOptions,
+};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum MethodArg {
+    Newton,
+    GoodBroyden,
+    BadBroyden,
+}
+
+impl From<MethodArg> for Method {
+    fn from(m: MethodArg) -> Self {
+        match m {
+            MethodArg::Newton => Method::Newton,
+            MethodArg::GoodBroyden => Method::GoodBroyden,
+            MethodArg::BadBroyden => Method::BadBroyden,
+        }
+    }
+}
+
+/// Solves a system of equations, written one `left = right` per line
+#[derive(Debug, Parser)]
+#[command(name = "equation-solver")]
+struct Args {
+    /// File with the equations, read from stdin if left out or `-`
+    file: Option<PathBuf>,
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Largest residual of a solution
+    #[arg(short, long)]
+    tolerance: Option<f64>,
+    /// Newton iterations per attempt
+    #[arg(short, long)]
+    iterations: Option<usize>,
+    /// Starting points tried before giving up
+    #[arg(short, long)]
+    attempts: Option<usize>,
+    /// Seed of the random starting points
+    #[arg(short, long)]
+    seed: Option<u64>,
+    /// Only accept real solutions, taking odd roots of negative numbers to be real
+    #[arg(short, long)]
+    real_only: bool,
+    /// Follow a homotopy from each starting point
+    #[arg(long)]
+    homotopy: bool,
+    /// Polish the solution to about 32 significant digits, which JSON gives as strings
+    #[arg(long)]
+    refine: bool,
+    /// Prove that exactly one root lies within a small box around the solution
+    #[arg(long)]
+    certify: bool,
+    /// Report the residual, the condition number and how many digits of each value are right
+    #[arg(long)]
+    verify: bool,
+    /// How Newton iterations get the Jacobian, where Broyden's methods update it instead of
+    /// taking finite differences every step
+    #[arg(short, long, value_enum, default_value_t = MethodArg::Newton)]
+    method: MethodArg,
+    /// Known value of a variable, as `name=value`
+    #[arg(short, long = "param", value_parser = parse_param)]
+    params: Vec<(String, f64)>,
+}
+
+fn parse_param(s: &str) -> Result<(String, f64), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected `name=value`, found `{}`", s))?;
+    let value = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("`{}` is not a number", value.trim()))?;
+    Ok((name.trim().to_string(), value))
+}
+
+/// Rounded to 6 decimals like the web page, which also hides leftover imaginary parts
+fn format_complex(v: Complex64) -> String {
+    let round = |x: f64| (x * 1e6).round() / 1e6 + 0.0;
+    let (re, im) = (round(v.re), round(v.im));
+    if im == 0.0 {
+        format!("{}", re)
+    } else if im > 0.0 {
+        format!("{} + {}i", re, im)
+    } else {
+        format!("{} - {}i", re, -im)
+    }
+}
+
+/// Units of the values in SI, leaving out the ones that are unknown or have none
+fn units(solution: &Solution) -> impl Iterator<Item = Option<String>> + '_ {
+    solution
+        .units
+        .iter()
+        .map(|unit| unit.filter(|u| !u.is_none()).map(|u| u.to_string()))
+}
+
+/// Prints the values in the order the variables first appear, which JSON gives as an array of
+/// `{ "name", "value", "unit" }` objects, the unit being `null` if it is unknown or there is none
+fn print_solution(solution: &Solution, format: Format) {
+    if let Some(precise) = &solution.precise {
+        return print_precise(solution, precise, format);
+    }
+    match format {
+        Format::Text => {
+            for ((name, v), unit) in solution.iter().zip(units(solution)) {
+                match unit {
+                    Some(unit) => println!("{} = {} {}", name, format_complex(v), unit),
+                    None => println!("{} = {}", name, format_complex(v)),
+                }
+            }
+        }
+        Format::Json => {
+            let values: Vec<serde_json::Value> = solution
+                .iter()
+                .zip(units(solution))
+                .map(|((name, v), unit)| {
+                    serde_json::json!({ "name": name, "value": [v.re, v.im], "unit": unit })
+                })
+                .collect();
+            println!("{}", serde_json::Value::Array(values))
+        }
+        Format::Csv => {
+            println!("variable,re,im,unit");
+            for ((name, v), unit) in solution.iter().zip(units(solution)) {
+                println!("{},{},{},{}", name, v.re, v.im, unit.unwrap_or_default())
+            }
+        }
+    }
+}
+
+/// Like `print_solution`, with every digit of the refined values, or as many as were verified
+fn print_precise(solution: &Solution, precise: &[ComplexDD], format: Format) {
+    let digits: Vec<usize> = match &solution.verification {
+        Some(verification) => solution
+            .values
+            .iter()
+            .zip(&verification.errors)
+            .map(|(v, error)| Verification::digits(*v, *error).max(1))
+            .collect(),
+        None => vec![DEFAULT_DIGITS; precise.len()],
+    };
+    let values = solution
+        .names
+        .iter()
+        .zip(precise.iter().zip(digits))
+        .zip(units(solution));
+    match format {
+        Format::Text => {
+            for ((name, (v, digits)), unit) in values {
+                let v = format!("{:.*}", digits, v);
+                match unit {
+                    Some(unit) if v.contains(' ') => println!("{} = ({}) {}", name, v, unit),
+                    Some(unit) => println!("{} = {} {}", name, v, unit),
+                    None => println!("{} = {}", name, v),
+                }
+            }
+        }
+        Format::Json => {
+            let values: Vec<serde_json::Value> = values
+                .map(|((name, (v, digits)), unit)| {
+                    let parts = [
+                        format!("{:.*}", digits, v.re),
+                        format!("{:.*}", digits, v.im),
+                    ];
+                    serde_json::json!({ "name": name, "value": parts, "unit": unit })
+                })
+                .collect();
+            println!("{}", serde_json::Value::Array(values))
+        }
+        Format::Csv => {
+            println!("variable,re,im,unit");
+            for ((name, (v, digits)), unit) in values {
+                let [re, im] = [v.re, v.im].map(|part| format!("{:.*}", digits, part));
+                println!("{},{},{},{}", name, re, im, unit.unwrap_or_default())
+            }
+        }
+    }
+}
+
+/// Prints notes on the solution, on stderr unless the output is text
+fn print_note(msg: &str, format: Format) {
+    match format {
+        Format::Text => println!("{}", msg),
+        Format::Json | Format::Csv => eprintln!("{}", msg),
+    }
+}
+
+/// The other solutions of a linear system with infinitely many, one direction per line
+fn print_null_space(solution: &Solution, format: Format) {
+    print_note(
+        "infinitely many solutions: the one above plus any combination of",
+        format,
+    );
+    for direction in &solution.null_space {
+        let values: Vec<String> = solution
+            .names
+            .iter()
+            .zip(direction)
+            .map(|(name, v)| format!("{} = {}", name, format_complex(*v)))
+            .collect();
+        print_note(&format!("  {}", values.join(", ")), format)
+    }
+}
+
+/// Whether the solution is certified
+fn print_certificate(solution: &Solution, format: Format) {
+    let msg = match &solution.enclosure {
+        Some(boxes) => {
+            let radius = boxes
+                .iter()
+                .zip(&solution.values)
+                .flat_map(|(b, v)| {
+                    [
+                        b.re.lo - v.re,
+                        b.re.hi - v.re,
+                        b.im.lo - v.im,
+                        b.im.hi - v.im,
+                    ]
+                })
+                .fold(0.0, |r: f64, d| r.max(d.abs()));
+            format!(
+                "certified: exactly one root lies within {:.1e} of the solution",
+                radius
+            )
+        }
+        None => "not certified: no box around the solution passed the Krawczyk test".into(),
+    };
+    print_note(&msg, format)
+}
+
+/// How far each value can be trusted
+fn print_verification(solution: &Solution, verification: &Verification, format: Format) {
+    let msg = format!(
+        "residual: {:.1e}, condition number: {:.1e}",
+        verification.residual, verification.condition
+    );
+    print_note(&msg, format);
+    for ((name, v), error) in solution.iter().zip(&verification.errors) {
+        let digits = Verification::digits(v, *error);
+        let msg = format!(
+            "{}: error about {:.1e}, {} significant digits",
+            name, error, digits
+        );
+        print_note(&msg, format)
+    }
+    if verification.multiple {
+        let msg =
+            "the root looks multiple, where the Jacobian is singular and fewer digits are right";
+        print_note(msg, format)
+    }
+}
+
+fn run(args: Args) -> Result<Solution, String> {
+    let source = match &args.file {
+        Some(path) if path.as_os_str() != "-" => fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?,
+        _ => {
+            let mut source = String::new();
+            io::stdin()
+                .read_to_string(&mut source)
+                .map_err(|e| format!("Could not read stdin: {}", e))?;
+            source
+        }
+    };
+
+    // blank lines and `#` comments are skipped, so remember where each equation came from
+    let mut lines = vec![];
+    let mut eqs = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (left, right) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected `left = right`", i + 1))?;
+        lines.push(i + 1);
+        eqs.push((left, right));
+    }
+
+    let system = parse_system(&eqs).map_err(|e| match e {
+        Error::Parse { eq, side, msg } | Error::Units { eq, side, msg } => {
+            let side = match side {
+                Side::Left => "left",
+                Side::Right => "right",
+            };
+            format!("line {}, {} side: {}", lines[eq], side, msg)
+        }
+        e => e.to_string(),
+    })?;
+
+    let mut options = SolverOptions {
+        params: args
+            .params
+            .into_iter()
+            .map(|(name, v)| (name, v.into()))
+            .collect(),
+        homotopy: args.homotopy,
+        real_only: args.real_only,
+        refine: args.refine,
+        certify: args.certify,
+        verify: args.verify,
+        method: args.method.into(),
+        ..SolverOptions::default()
+    };
+    options.branch.real_roots = args.real_only;
+    if let Some(tolerance) = args.tolerance {
+        options.tolerance = tolerance
+    }
+    if let Some(iterations) = args.iterations {
+        options.iterations = iterations
+    }
+    if let Some(attempts) = args.attempts {
+        options.attempts = attempts
+    }
+    if let Some(seed) = args.seed {
+        options.seed = seed
+    }
+
+    system.solve_with(&options).map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let format = args.format;
+    let certify = args.certify;
+    match run(args) {
+        Ok(solution) => {
+            print_solution(&solution, format);
+            if !solution.null_space.is_empty() {
+                print_null_space(&solution, format)
+            }
+            if solution.multiplicity > 1 {
+                let msg = format!("the root has multiplicity {}", solution.multiplicity);
+                print_note(&msg, format)
+            }
+            if certify {
+                print_certificate(&solution, format)
+            }
+            if let Some(verification) = &solution.verification {
+                print_verification(&solution, verification, format)
+            }
+            ExitCode::SUCCESS
+        }
+        Err(msg) => {
+            eprintln!("error: {}", msg);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,4 +1,9 @@
-use super::operators::{BinOp, UnaryOp};
+use super::{
+    operators::{BinOp, UnaryOp},
+    symbols::VarId,
+    units::Unit,
+};
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 macro_rules! functions {
@@ -7,7 +12,7 @@ macro_rules! functions {
             $name:ident: $str:literal,
         )*
     ) => {
-        #[derive(Debug, Clone, Copy)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
         pub enum Function {
             $(
                 $name,
@@ -15,7 +20,7 @@ macro_rules! functions {
         }
 
         impl Function {
-            pub fn from_str(s: &str) -> Option<Self> {
+            pub fn from_name(s: &str) -> Option<Self> {
                 match s {
                     $(
                         $str => Some(Self::$name),
@@ -23,9 +28,16 @@ macro_rules! functions {
                     _ => None
                 }
             }
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(
+                        Self::$name => $str,
+                    )*
+                }
+            }
         }
 
-        #[wasm_bindgen]
+        #[cfg_attr(feature = "wasm", wasm_bindgen)]
         pub fn is_not_var(s: &str) -> bool {
             match s {
                 "e" | "pi" | "i" $(| $str)* => true,
@@ -33,7 +45,7 @@ macro_rules! functions {
             }
         }
 
-        #[wasm_bindgen]
+        #[cfg_attr(feature = "wasm", wasm_bindgen)]
         pub fn funcs() -> String {
             [$($str,)*].join(", ")
         }
@@ -63,10 +75,13 @@ functions! {
     Arg: "arg",
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ExprNode {
     Number(f64),
-    Var(u16),
+    /// A number as written, with its nearest `f64`, so that more precise scalars can read
+    /// every digit of it
+    Decimal(f64, Box<str>),
+    Var(VarId),
 
     BinOp(Box<ExprNode>, BinOp, Box<ExprNode>),
     UnaryOp(UnaryOp, Box<ExprNode>),
@@ -75,7 +90,51 @@ pub enum ExprNode {
     Pi,
     I,
 
+    /// One of a unit, like the `[kg]` in `2 [kg]`
+    Unit(Unit),
+
     Abs(Box<ExprNode>),
 
     Func(Function, Box<ExprNode>),
 }
+
+impl ExprNode {
+    /// `first op₁ a₁ op₂ a₂ …` computed from left to right, with every run of the same
+    /// associative operator grouped into a balanced tree, so long sums and products stay
+    /// shallow
+    pub fn chain(first: ExprNode, rest: Vec<(BinOp, ExprNode)>) -> ExprNode {
+        fn balanced(op: BinOp, mut nodes: Vec<ExprNode>) -> ExprNode {
+            if nodes.len() == 1 {
+                return nodes.pop().unwrap();
+            }
+            let right = nodes.split_off(nodes.len().div_ceil(2));
+            ExprNode::BinOp(
+                Box::new(balanced(op, nodes)),
+                op,
+                Box::new(balanced(op, right)),
+            )
+        }
+
+        let mut out = first;
+        let mut rest = rest.into_iter().peekable();
+        while let Some((op, node)) = rest.next() {
+            let mut run = vec![out, node];
+            if op.is_associative() {
+                while let Some((_, node)) = rest.next_if(|(next, _)| *next == op) {
+                    run.push(node)
+                }
+            }
+            out = balanced(op, run);
+        }
+        out
+    }
+
+    /// Length of the longest path from the root to a leaf
+    pub fn depth(&self) -> usize {
+        match self {
+            ExprNode::BinOp(a, _, b) => a.depth().max(b.depth()) + 1,
+            ExprNode::UnaryOp(_, v) | ExprNode::Abs(v) | ExprNode::Func(_, v) => v.depth() + 1,
+            _ => 0,
+        }
+    }
+}
@@ -34,6 +34,11 @@ pub enum Token {
     #[token("|")]
     Pipe,
 
+    #[token("[")]
+    OpenBracket,
+    #[token("]")]
+    ClosedBracket,
+
     // Or regular expressions.
     #[regex(r#"[A-Za-z_][A-Za-z0-9_']*"#)]
     Identifier,
@@ -70,6 +75,8 @@ impl Token {
             Token::Pipe => "`|`",
             Token::OpenParen => "`(`",
             Token::ClosedParen => "`)`",
+            Token::OpenBracket => "`[`",
+            Token::ClosedBracket => "`]`",
             Token::Identifier => "variable",
             Token::End => "equation end",
             Token::Error => "unknown",
@@ -1,4 +1,7 @@
 pub mod ast;
-pub mod lexer;
+pub(crate) mod lexer;
 pub mod operators;
-pub mod parser;
+pub(crate) mod parser;
+pub mod printing;
+pub mod symbols;
+pub mod units;
@@ -49,28 +49,46 @@ macro_rules! operators {
 
         impl BinOp {
             pub fn name(self) -> &'static str {
+                self.to_token().name()
+            }
+            pub fn to_token(self) -> Token {
                 match self {
                     $(
                         $($(
-                            BinOp::$l_tok => Token::$l_tok.name(),
+                            BinOp::$l_tok => Token::$l_tok,
                         )+)?
                         $($(
-                            BinOp::$r_tok => Token::$r_tok.name(),
+                            BinOp::$r_tok => Token::$r_tok,
                         )+)?
                     )+
                 }
             }
+            pub fn prec(self) -> usize {
+                OP_LIST
+                    .iter()
+                    .position(|(typ, toks)| *typ != OpType::Unary && toks.contains(&self.to_token()))
+                    .unwrap()
+            }
+            pub fn op_type(self) -> OpType {
+                prec_type(self.prec())
+            }
         }
         impl UnaryOp {
             pub fn name(self) -> &'static str {
+                self.to_token().name()
+            }
+            pub fn to_token(self) -> Token {
                 match self {
                     $(
                         $($(
-                            UnaryOp::$u_tok => Token::$u_tok.name(),
+                            UnaryOp::$u_tok => Token::$u_tok,
                         )+)?
                     )+
                 }
             }
+            pub fn prec(self) -> usize {
+                unary_prec(self.to_token()).unwrap()
+            }
         }
 
         impl Token {
@@ -141,3 +159,10 @@ operators! {
     Left => [Mult, Div, Mod];
     Right => [Pow];
 }
+
+impl BinOp {
+    /// Whether chains of the operator give the same value however they are grouped
+    pub fn is_associative(self) -> bool {
+        matches!(self, BinOp::Plus | BinOp::Mult)
+    }
+}
@@ -1,6 +1,6 @@
 #![deny(unused_must_use)]
 
-use std::{collections::HashMap, ops::Range};
+use std::ops::Range;
 
 use logos::{Lexer, Logos};
 
@@ -10,11 +10,19 @@ use super::{
     ast::ExprNode,
     lexer::{NextOrEnd, Token},
     operators,
+    symbols::{SymbolTable, VarId},
+    units::{Dimension, Unit},
 };
 
+/// Most parentheses, bars, functions and unary operators around a part of an expression
+const MAX_NESTING: usize = 100;
+/// Deepest expression tree, which chains of operators other than `+` and `*` count towards
+const MAX_DEPTH: usize = 400;
+
 pub struct Parser<'a> {
     lexer: Lexer<'a, Token>,
-    name_map: &'a mut HashMap<String, u16>,
+    symbols: &'a mut SymbolTable,
+    nesting: usize,
 }
 
 pub type ParseResult<T> = Result<T, String>;
@@ -24,11 +32,12 @@ pub fn unexpected_err_str(found: Token, exp: &str) -> String {
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(code: &'a str, map: &'a mut HashMap<String, u16>) -> Self {
+    pub fn new(code: &'a str, symbols: &'a mut SymbolTable) -> Self {
         let lexer = Token::lexer(code);
         Parser {
             lexer,
-            name_map: map,
+            symbols,
+            nesting: 0,
         }
     }
 
@@ -56,6 +65,7 @@ impl<'a> Parser<'a> {
     pub fn next_is(&self, tok: Token) -> bool {
         self.peek() == tok
     }
+    #[allow(dead_code)]
     pub fn next_are(&self, toks: &[Token]) -> bool {
         let mut peek = self.lexer.clone();
         for tok in toks {
@@ -86,21 +96,28 @@ impl<'a> Parser<'a> {
         self.expect_tok_named(expect, expect.name())
     }
 
-    pub fn get_name_id(&mut self, name: String) -> u16 {
-        match self.name_map.get(&name) {
-            Some(v) => *v,
-            None => {
-                let id = self.name_map.len() as u16;
-                self.name_map.insert(name, id);
-                id
-            }
+    pub fn get_name_id(&mut self, name: &str) -> ParseResult<VarId> {
+        self.symbols.intern(name)
+    }
+
+    /// Parses a part of the expression nested in another, keeping the recursion bounded
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        if self.nesting == MAX_NESTING {
+            return Err("Expression is nested too deeply".into());
         }
+        self.nesting += 1;
+        let out = f(self);
+        self.nesting -= 1;
+        out
     }
 
     pub fn parse_unit(&mut self) -> ParseResult<ExprNode> {
         Ok(match self.next() {
             Token::Number => {
-                let mut expr = ExprNode::Number(self.slice().parse().unwrap());
+                let Ok(v) = self.slice().parse() else {
+                    return Err(format!("Invalid number `{}`", self.slice()));
+                };
+                let mut expr = ExprNode::Decimal(v, self.slice().into());
                 match self.peek() {
                     Token::I => {
                         self.next();
@@ -110,12 +127,21 @@ impl<'a> Parser<'a> {
                             Box::new(ExprNode::I),
                         )
                     }
+                    Token::OpenBracket => {
+                        self.next();
+                        expr = ExprNode::BinOp(
+                            Box::new(expr),
+                            operators::BinOp::Mult,
+                            Box::new(self.parse_unit_literal()?),
+                        )
+                    }
                     Token::Identifier => {
                         self.next();
+                        let name = self.slice().to_string();
                         expr = ExprNode::BinOp(
                             Box::new(expr),
                             operators::BinOp::Mult,
-                            Box::new(ExprNode::Var(self.get_name_id(self.slice().into()))),
+                            Box::new(ExprNode::Var(self.get_name_id(&name)?)),
                         )
                     }
                     _ => (),
@@ -126,61 +152,142 @@ impl<'a> Parser<'a> {
             Token::Pi => ExprNode::Pi,
             Token::I => ExprNode::I,
             Token::OpenParen => {
-                let v = self.parse_expr()?;
+                let v = self.nested(Self::parse_expr)?;
                 self.expect_tok(Token::ClosedParen)?;
                 v
             }
             Token::Pipe => {
-                let v = self.parse_expr()?;
+                let v = self.nested(Self::parse_expr)?;
                 self.expect_tok(Token::Pipe)?;
                 ExprNode::Abs(Box::new(v))
             }
+            Token::OpenBracket => self.parse_unit_literal()?,
             Token::Identifier => {
                 let v = self.slice().to_string();
                 if self.skip_tok(Token::OpenParen) {
-                    let Some(func) = Function::from_str(&v) else {
-                        return Err(format!("Unknown function `{}`", v))
+                    let Some(func) = Function::from_name(&v) else {
+                        return Err(format!("Unknown function `{}`", v));
                     };
-                    let v = self.parse_expr()?;
+                    let v = self.nested(Self::parse_expr)?;
                     self.expect_tok(Token::ClosedParen)?;
                     ExprNode::Func(func, Box::new(v))
                 } else {
-                    if Function::from_str(&v).is_some() {
+                    if Function::from_name(&v).is_some() {
                         return Err(format!("Cannot use variable with function name `{}`", v));
                     }
-                    ExprNode::Var(self.get_name_id(v))
+                    ExprNode::Var(self.get_name_id(&v)?)
                 }
             }
-            t => return Err(unexpected_err_str(t, "expression")),
+            t => match t.to_unary_op() {
+                // unary operators after tighter binding operators, like `2 * -x` or `2^-x`,
+                // only apply to the operand that follows them
+                Some(op) => ExprNode::UnaryOp(
+                    op,
+                    Box::new(self.nested(|p| p.parse_op(operators::OP_COUNT - 1))?),
+                ),
+                None => return Err(unexpected_err_str(t, "expression")),
+            },
         })
     }
 
+    /// Parses a unit like `kg*m/s^2` after its opening `[`, up to and including the `]`
+    fn parse_unit_literal(&mut self) -> ParseResult<ExprNode> {
+        let start = self.span().end;
+        let mut unit = Unit::new("", 1.0, Dimension::NONE);
+        if self.next_is(Token::Number) {
+            // only as in `1/s`
+            if self.peek_slice() != "1" {
+                return Err(format!("Expected unit, found `{}`", self.peek_slice()));
+            }
+            self.next();
+        } else {
+            unit = self.parse_unit_factor(unit, 1.0)?;
+        }
+        loop {
+            unit = match self.peek() {
+                Token::Mult => {
+                    self.next();
+                    self.parse_unit_factor(unit, 1.0)?
+                }
+                Token::Div => {
+                    self.next();
+                    self.parse_unit_factor(unit, -1.0)?
+                }
+                Token::Identifier => self.parse_unit_factor(unit, 1.0)?,
+                _ => break,
+            }
+        }
+        let end = self.span().end;
+        self.expect_tok(Token::ClosedBracket)?;
+        unit.name = self.lexer.source()[start..end].trim().to_string();
+        Ok(ExprNode::Unit(unit))
+    }
+
+    /// Multiplies `unit` by the next unit symbol, with its exponent multiplied by `sign`
+    fn parse_unit_factor(&mut self, unit: Unit, sign: f64) -> ParseResult<Unit> {
+        self.expect_tok_named(Token::Identifier, "unit")?;
+        let Some(factor) = Unit::from_symbol(self.slice()) else {
+            return Err(format!("Unknown unit `{}`", self.slice()));
+        };
+        let mut exp = 1.0;
+        if self.skip_tok(Token::Pow) {
+            let negative = self.skip_tok(Token::Minus);
+            self.expect_tok_named(Token::Number, "exponent")?;
+            let Ok(v) = self.slice().parse::<f64>() else {
+                return Err(format!("Invalid number `{}`", self.slice()));
+            };
+            exp = if negative { -v } else { v };
+        }
+        Ok(unit.mul(&factor, sign * exp))
+    }
+
     pub fn parse_expr(&mut self) -> ParseResult<ExprNode> {
         self.parse_op(0)
     }
 
+    fn parse_next_prec(&mut self, prec: usize) -> ParseResult<ExprNode> {
+        if prec + 1 < operators::OP_COUNT {
+            self.parse_op(prec + 1)
+        } else {
+            self.parse_unit()
+        }
+    }
+
     pub fn parse_op(&mut self, prec: usize) -> ParseResult<ExprNode> {
-        let next_prec = operators::next_infix(prec);
+        if operators::prec_type(prec) == operators::OpType::Unary {
+            if operators::unary_prec(self.peek()) == Some(prec) {
+                let op = self.next().to_unary_op().unwrap();
+                let v = self.nested(|p| p.parse_op(prec))?;
+                return Ok(ExprNode::UnaryOp(op, Box::new(v)));
+            }
+            return self.parse_next_prec(prec);
+        }
 
-        let mut left = match next_prec {
-            Some(next_prec) => self.parse_op(next_prec)?,
-            None => self.parse_unit()?,
-        };
+        let first = self.parse_next_prec(prec)?;
+        let mut depth = first.depth();
+        let mut rest: Vec<(operators::BinOp, ExprNode)> = vec![];
 
         while operators::is_infix_prec(self.peek(), prec) {
-            let op = self.next();
+            let op = self.next().to_bin_op().unwrap();
             let right = if operators::prec_type(prec) == operators::OpType::Left {
-                match next_prec {
-                    Some(next_prec) => self.parse_op(next_prec)?,
-                    None => self.parse_unit()?,
-                }
+                self.parse_next_prec(prec)?
             } else {
-                self.parse_op(prec)?
+                self.nested(|p| p.parse_op(prec))?
             };
-            left = ExprNode::BinOp(Box::new(left), op.to_bin_op().unwrap(), Box::new(right))
+            // runs of an associative operator are balanced, so only the start of one adds a level
+            let same_run = op.is_associative() && rest.last().map(|(last, _)| *last) == Some(op);
+            depth = depth.max(right.depth()) + usize::from(!same_run);
+            if depth > MAX_DEPTH {
+                return Err("Expression is too long".into());
+            }
+            rest.push((op, right))
         }
 
-        Ok(left)
+        let out = ExprNode::chain(first, rest);
+        if out.depth() > MAX_DEPTH {
+            return Err("Expression is too long".into());
+        }
+        Ok(out)
     }
 
     pub fn parse(&mut self) -> ParseResult<ExprNode> {
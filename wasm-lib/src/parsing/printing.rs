@@ -0,0 +1,255 @@
+use std::fmt::{self, Display, Write};
+
+use super::{
+    ast::{ExprNode, Function},
+    operators::{self, BinOp, OpType, UnaryOp},
+    symbols::{SymbolTable, VarId},
+};
+
+/// Precedence of atoms, which never need parentheses
+const ATOM_PREC: usize = operators::OP_COUNT;
+/// Precedence of the operand parsed after a unary operator that directly follows a binary one
+const TIGHTEST_PREC: usize = operators::OP_COUNT - 1;
+
+pub struct ExprDisplay<'a> {
+    node: &'a ExprNode,
+    symbols: &'a SymbolTable,
+}
+
+impl ExprNode {
+    /// Precedence of the outermost operator, `latex` treating fractions as atoms
+    fn prec(&self, latex: bool) -> usize {
+        match self {
+            ExprNode::BinOp(_, BinOp::Div, _) if latex => ATOM_PREC,
+            ExprNode::BinOp(a, BinOp::Mult, b) if is_quantity(a, b) => ATOM_PREC,
+            ExprNode::BinOp(_, op, _) => op.prec(),
+            ExprNode::UnaryOp(op, _) => op.prec(),
+            ExprNode::Number(v) if *v < 0.0 => UnaryOp::Minus.prec(),
+            _ => ATOM_PREC,
+        }
+    }
+
+    /// Whether the node needs parentheses as the left or right operand of `op`
+    fn needs_parens(&self, op: BinOp, right: bool, latex: bool) -> bool {
+        let (prec, child_prec) = (op.prec(), self.prec(latex));
+        if child_prec > prec {
+            return false;
+        }
+        if child_prec == prec {
+            // balanced sums and products read the same without their parentheses
+            if let ExprNode::BinOp(_, child_op, _) = self {
+                if right && *child_op == op && op.is_associative() {
+                    return false;
+                }
+            }
+            return (op.op_type() == OpType::Left) == right;
+        }
+        // unary operators after a binary operator bind only to the tightest operand, as in `2 * -x^2`
+        match self {
+            ExprNode::UnaryOp(_, v) => !right || v.prec(latex) < TIGHTEST_PREC,
+            ExprNode::Number(_) | ExprNode::Decimal(..) => !right,
+            _ => true,
+        }
+    }
+
+    /// Displays the expression as source with as few parentheses as possible
+    pub fn display<'a>(&'a self, symbols: &'a SymbolTable) -> ExprDisplay<'a> {
+        ExprDisplay {
+            node: self,
+            symbols,
+        }
+    }
+
+    pub fn latex(&self, symbols: &SymbolTable) -> String {
+        let mut out = String::new();
+        self.write_latex(&mut out, symbols).unwrap();
+        out
+    }
+
+    fn write_latex(&self, f: &mut String, symbols: &SymbolTable) -> fmt::Result {
+        let paren = |f: &mut String, node: &ExprNode, parens: bool| {
+            if parens {
+                f.push_str("\\left(");
+                node.write_latex(f, symbols)?;
+                f.push_str("\\right)");
+                Ok(())
+            } else {
+                node.write_latex(f, symbols)
+            }
+        };
+
+        match self {
+            ExprNode::Number(v) | ExprNode::Decimal(v, _) => write!(f, "{}", v),
+            ExprNode::Var(id) => write!(f, "{}", latex_name(&var_name(symbols, *id))),
+            ExprNode::BinOp(a, BinOp::Mult, b) if is_quantity(a, b) => {
+                a.write_latex(f, symbols)?;
+                f.push_str("\\,");
+                b.write_latex(f, symbols)
+            }
+            ExprNode::BinOp(a, BinOp::Div, b) => {
+                f.push_str("\\frac{");
+                a.write_latex(f, symbols)?;
+                f.push_str("}{");
+                b.write_latex(f, symbols)?;
+                f.push('}');
+                Ok(())
+            }
+            ExprNode::BinOp(a, BinOp::Pow, b) => {
+                let frac_base = matches!(**a, ExprNode::BinOp(_, BinOp::Div, _));
+                paren(f, a, frac_base || a.needs_parens(BinOp::Pow, false, true))?;
+                f.push_str("^{");
+                b.write_latex(f, symbols)?;
+                f.push('}');
+                Ok(())
+            }
+            ExprNode::BinOp(a, op, b) => {
+                paren(f, a, a.needs_parens(*op, false, true))?;
+                f.push_str(match op {
+                    BinOp::Plus => " + ",
+                    BinOp::Minus => " - ",
+                    BinOp::Mult => " \\cdot ",
+                    BinOp::Mod => " \\bmod ",
+                    BinOp::Div | BinOp::Pow => unreachable!(),
+                });
+                paren(f, b, b.needs_parens(*op, true, true))
+            }
+            ExprNode::UnaryOp(op, v) => {
+                f.push_str(unary_str(*op));
+                paren(f, v, v.prec(true) < op.prec())
+            }
+            ExprNode::E => write!(f, "e"),
+            ExprNode::Pi => write!(f, "\\pi"),
+            ExprNode::I => write!(f, "i"),
+            ExprNode::Unit(unit) => write!(f, "\\mathrm{{{}}}", unit.name),
+            ExprNode::Abs(v) => {
+                f.push_str("\\left|");
+                v.write_latex(f, symbols)?;
+                f.push_str("\\right|");
+                Ok(())
+            }
+            ExprNode::Func(Function::Sqrt, v) => {
+                f.push_str("\\sqrt{");
+                v.write_latex(f, symbols)?;
+                f.push('}');
+                Ok(())
+            }
+            ExprNode::Func(Function::Cbrt, v) => {
+                f.push_str("\\sqrt[3]{");
+                v.write_latex(f, symbols)?;
+                f.push('}');
+                Ok(())
+            }
+            ExprNode::Func(func, v) => {
+                f.push_str(latex_func(*func));
+                paren(f, v, true)
+            }
+        }
+    }
+}
+
+impl Display for ExprDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbols = self.symbols;
+        let paren = |f: &mut fmt::Formatter<'_>, node: &ExprNode, parens: bool| {
+            if parens {
+                write!(f, "({})", node.display(symbols))
+            } else {
+                write!(f, "{}", node.display(symbols))
+            }
+        };
+
+        match self.node {
+            ExprNode::Number(v) | ExprNode::Decimal(v, _) => write!(f, "{}", v),
+            ExprNode::Var(id) => write!(f, "{}", var_name(symbols, *id)),
+            ExprNode::BinOp(a, BinOp::Mult, b) if is_quantity(a, b) => {
+                write!(f, "{} {}", a.display(symbols), b.display(symbols))
+            }
+            ExprNode::BinOp(a, op, b) => {
+                paren(f, a, a.needs_parens(*op, false, false))?;
+                f.write_str(match op {
+                    BinOp::Plus => " + ",
+                    BinOp::Minus => " - ",
+                    BinOp::Mult => " * ",
+                    BinOp::Div => " / ",
+                    BinOp::Mod => " % ",
+                    BinOp::Pow => "^",
+                })?;
+                paren(f, b, b.needs_parens(*op, true, false))
+            }
+            ExprNode::UnaryOp(op, v) => {
+                f.write_str(unary_str(*op))?;
+                paren(f, v, v.prec(false) < op.prec())
+            }
+            ExprNode::E => write!(f, "e"),
+            ExprNode::Pi => write!(f, "pi"),
+            ExprNode::I => write!(f, "i"),
+            ExprNode::Unit(unit) => write!(f, "[{}]", unit.name),
+            ExprNode::Abs(v) => write!(f, "|{}|", v.display(symbols)),
+            ExprNode::Func(func, v) => write!(f, "{}({})", func.name(), v.display(symbols)),
+        }
+    }
+}
+
+/// Whether `a * b` is a number with a unit, written as `2 [kg]`
+fn is_quantity(a: &ExprNode, b: &ExprNode) -> bool {
+    match (a, b) {
+        (ExprNode::Number(v), ExprNode::Unit(_)) => *v >= 0.0,
+        (ExprNode::Decimal(..), ExprNode::Unit(_)) => true,
+        _ => false,
+    }
+}
+
+fn unary_str(op: UnaryOp) -> &'static str {
+    match op {
+        UnaryOp::Plus => "+",
+        UnaryOp::Minus => "-",
+    }
+}
+
+fn var_name(symbols: &SymbolTable, id: VarId) -> String {
+    symbols
+        .name(id)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("${}", id))
+}
+
+/// Multi-letter names are set upright, and anything after the first `_` becomes a subscript
+fn latex_name(name: &str) -> String {
+    let (base, sub) = match name.split_once('_') {
+        Some((base, sub)) => (base, Some(sub)),
+        None => (name, None),
+    };
+    let letters = base.trim_end_matches('\'');
+    let primes = &base[letters.len()..];
+
+    let mut out = if letters.chars().count() > 1 {
+        format!("\\mathrm{{{}}}{}", letters, primes)
+    } else {
+        base.to_string()
+    };
+    if let Some(sub) = sub {
+        write!(out, "_{{{}}}", sub.replace('_', "\\_")).unwrap();
+    }
+    out
+}
+
+fn latex_func(func: Function) -> &'static str {
+    match func {
+        Function::Sin => "\\sin",
+        Function::Cos => "\\cos",
+        Function::Tan => "\\tan",
+        Function::SinH => "\\sinh",
+        Function::CosH => "\\cosh",
+        Function::TanH => "\\tanh",
+        Function::Asin => "\\arcsin",
+        Function::Acos => "\\arccos",
+        Function::Atan => "\\arctan",
+        Function::AsinH => "\\operatorname{asinh}",
+        Function::AcosH => "\\operatorname{acosh}",
+        Function::AtanH => "\\operatorname{atanh}",
+        Function::Sqrt => "\\sqrt",
+        Function::Cbrt => "\\sqrt[3]",
+        Function::Ln => "\\ln",
+        Function::Arg => "\\arg",
+    }
+}
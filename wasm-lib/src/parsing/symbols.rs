@@ -0,0 +1,126 @@
+use std::{collections::HashMap, convert::TryFrom};
+
+use num_complex::Complex64;
+
+use super::units::Dimension;
+
+/// Index of a variable in its `SymbolTable`
+pub type VarId = u32;
+
+/// Whether a variable is solved for or given a value beforehand. Parameters have to be given
+/// one in `SolverOptions::params`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymbolKind {
+    #[default]
+    Unknown,
+    Parameter,
+}
+
+/// A variable and what is known about it besides the equations
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Smallest and largest real value the variable may take, which random starting values
+    /// also lie within
+    pub bounds: Option<(f64, f64)>,
+    /// Value to start solving from instead of a random one
+    pub initial: Option<Complex64>,
+    /// SI units of the variable, inferred from the equations if not given
+    pub unit: Option<Dimension>,
+}
+
+impl Symbol {
+    pub fn new(name: impl Into<String>) -> Self {
+        Symbol {
+            name: name.into(),
+            kind: SymbolKind::default(),
+            bounds: None,
+            initial: None,
+            unit: None,
+        }
+    }
+}
+
+/// Every variable of a system, numbered in the order they were added
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    symbols: Vec<Symbol>,
+    ids: HashMap<String, VarId>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable::default()
+    }
+
+    /// Id of the variable called `name`, adding it if it is new
+    pub fn intern(&mut self, name: &str) -> Result<VarId, String> {
+        if let Some(id) = self.ids.get(name) {
+            return Ok(*id);
+        }
+        let Ok(id) = VarId::try_from(self.symbols.len()) else {
+            return Err(format!(
+                "Too many variables, at most {} are allowed",
+                VarId::MAX
+            ));
+        };
+        self.symbols.push(Symbol::new(name));
+        self.ids.insert(name.to_string(), id);
+        Ok(id)
+    }
+
+    pub fn id(&self, name: &str) -> Option<VarId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: VarId) -> Option<&str> {
+        self.get(id).map(|s| s.name.as_str())
+    }
+
+    pub fn get(&self, id: VarId) -> Option<&Symbol> {
+        self.symbols.get(id as usize)
+    }
+
+    pub fn get_mut(&mut self, id: VarId) -> Option<&mut Symbol> {
+        self.symbols.get_mut(id as usize)
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Every symbol, by id
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.symbols.iter().map(|s| s.name.clone()).collect()
+    }
+
+    /// The table of the symbols for which `keep` holds, renumbered in the same order, and the
+    /// new id of every old one
+    pub fn subset(&self, keep: &[bool]) -> (SymbolTable, Vec<Option<VarId>>) {
+        let mut table = SymbolTable::new();
+        let ids = self
+            .symbols
+            .iter()
+            .zip(keep)
+            .map(|(symbol, keep)| {
+                keep.then(|| {
+                    // never more symbols than `self` has, so the id fits
+                    let id = table.symbols.len() as VarId;
+                    table.symbols.push(symbol.clone());
+                    table.ids.insert(symbol.name.clone(), id);
+                    id
+                })
+            })
+            .collect();
+        (table, ids)
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt::{self, Display};
+
+/// Names of the SI base units, in the order of the exponents of a `Dimension`
+const BASE_UNITS: [&str; 7] = ["kg", "m", "s", "A", "K", "mol", "cd"];
+/// Exponents below this count as zero
+const EXPONENT_TOLERANCE: f64 = 1e-9;
+
+/// Exponents of the SI base units `kg`, `m`, `s`, `A`, `K`, `mol` and `cd`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Dimension(pub [f64; 7]);
+
+/// Derived units that solutions are shown in when they match exactly
+const DERIVED: [(&str, Dimension); 6] = [
+    ("N", Dimension([1.0, 1.0, -2.0, 0.0, 0.0, 0.0, 0.0])),
+    ("J", Dimension([1.0, 2.0, -2.0, 0.0, 0.0, 0.0, 0.0])),
+    ("W", Dimension([1.0, 2.0, -3.0, 0.0, 0.0, 0.0, 0.0])),
+    ("Pa", Dimension([1.0, -1.0, -2.0, 0.0, 0.0, 0.0, 0.0])),
+    ("C", Dimension([0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0])),
+    ("V", Dimension([1.0, 2.0, -3.0, -1.0, 0.0, 0.0, 0.0])),
+];
+
+impl Dimension {
+    pub const NONE: Dimension = Dimension([0.0; 7]);
+
+    fn base(i: usize) -> Dimension {
+        let mut out = Dimension::NONE;
+        out.0[i] = 1.0;
+        out
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.0.iter().all(|e| e.abs() < EXPONENT_TOLERANCE)
+    }
+
+    /// `self + other * factor`, the dimension of `self * other^factor`
+    pub fn add(mut self, other: Dimension, factor: f64) -> Dimension {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a += b * factor
+        }
+        self
+    }
+
+    pub fn scale(self, factor: f64) -> Dimension {
+        Dimension::NONE.add(self, factor)
+    }
+}
+
+impl Display for Dimension {
+    /// Writes the dimension as a product of SI units, like `kg*m/s^2`, or a derived unit
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((name, _)) = DERIVED
+            .iter()
+            .find(|(_, dim)| dim.add(*self, -1.0).is_none())
+        {
+            return f.write_str(name);
+        }
+        let power = |name: &str, e: f64| {
+            let e = (e * 1e6).round() / 1e6;
+            if e == 1.0 {
+                name.to_string()
+            } else {
+                format!("{}^{}", name, e)
+            }
+        };
+        let exponents = BASE_UNITS.iter().zip(self.0);
+        let num: Vec<String> = exponents
+            .clone()
+            .filter(|(_, e)| *e >= EXPONENT_TOLERANCE)
+            .map(|(name, e)| power(name, e))
+            .collect();
+        let den: Vec<String> = exponents
+            .filter(|(_, e)| *e <= -EXPONENT_TOLERANCE)
+            .map(|(name, e)| power(name, -e))
+            .collect();
+
+        let num = if num.is_empty() {
+            "1".to_string()
+        } else {
+            num.join("*")
+        };
+        match den.len() {
+            0 => f.write_str(&num),
+            1 => write!(f, "{}/{}", num, den[0]),
+            _ => write!(f, "{}/({})", num, den.join("*")),
+        }
+    }
+}
+
+/// A unit as a multiple of a product of SI base units
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    /// The unit as it was written
+    pub name: String,
+    /// Value of one of this unit in SI base units
+    pub scale: f64,
+    pub dim: Dimension,
+}
+
+/// Prefixes that can go before the symbols of metric units
+const PREFIXES: [(&str, f64); 9] = [
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("u", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+];
+
+impl Unit {
+    pub fn new(name: &str, scale: f64, dim: Dimension) -> Self {
+        Unit {
+            name: name.to_string(),
+            scale,
+            dim,
+        }
+    }
+
+    /// A unit without a prefix, and whether it can take one
+    fn unprefixed(symbol: &str) -> Option<(f64, Dimension, bool)> {
+        Some(match symbol {
+            "m" => (1.0, Dimension::base(1), true),
+            "g" => (1e-3, Dimension::base(0), true),
+            "s" => (1.0, Dimension::base(2), true),
+            "A" => (1.0, Dimension::base(3), true),
+            "K" => (1.0, Dimension::base(4), true),
+            "mol" => (1.0, Dimension::base(5), true),
+            "cd" => (1.0, Dimension::base(6), true),
+            "N" => (1.0, DERIVED[0].1, true),
+            "J" => (1.0, DERIVED[1].1, true),
+            "W" => (1.0, DERIVED[2].1, true),
+            "Pa" => (1.0, DERIVED[3].1, true),
+            "C" => (1.0, DERIVED[4].1, true),
+            "V" => (1.0, DERIVED[5].1, true),
+            "Hz" => (1.0, Dimension::base(2).scale(-1.0), true),
+            "ohm" => (1.0, Dimension([1.0, 2.0, -3.0, -2.0, 0.0, 0.0, 0.0]), true),
+            "L" => (1e-3, Dimension::base(1).scale(3.0), true),
+            "min" => (60.0, Dimension::base(2), false),
+            "h" => (3600.0, Dimension::base(2), false),
+            "rad" => (1.0, Dimension::NONE, false),
+            "deg" => (std::f64::consts::PI / 180.0, Dimension::NONE, false),
+            _ => return None,
+        })
+    }
+
+    /// Looks up a unit symbol like `kg` or `ms`, preferring an unprefixed unit if both match
+    pub fn from_symbol(symbol: &str) -> Option<Unit> {
+        if let Some((scale, dim, _)) = Unit::unprefixed(symbol) {
+            return Some(Unit::new(symbol, scale, dim));
+        }
+        PREFIXES.iter().find_map(|(prefix, factor)| {
+            let (scale, dim, prefixable) = Unit::unprefixed(symbol.strip_prefix(prefix)?)?;
+            prefixable.then(|| Unit::new(symbol, scale * factor, dim))
+        })
+    }
+
+    pub fn mul(self, other: &Unit, exp: f64) -> Unit {
+        Unit {
+            name: self.name,
+            scale: self.scale * other.scale.powf(exp),
+            dim: self.dim.add(other.dim, exp),
+        }
+    }
+}
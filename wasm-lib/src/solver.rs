@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use num_complex::Complex64;
+
+use crate::{
+    equation::{
+        double_double::ComplexDD,
+        interval::ComplexInterval,
+        linear::LinearSolution,
+        running::Branch,
+        system::{Method, NewtonStats, System, TOLERANCE},
+        verification::Verification,
+    },
+    parsing::{
+        symbols::{Symbol, SymbolKind},
+        units::Dimension,
+    },
+    utils::Rng,
+    Error,
+};
+
+/// Initial values are picked with real and imaginary parts in `-RANDOM_RANGE..RANDOM_RANGE`
+const RANDOM_RANGE: f64 = 50.0;
+/// Imaginary parts below this, relative to the real part, count as zero for `real_only`
+const IMAGINARY_TOLERANCE: f64 = 1e-6;
+
+#[derive(Debug, Clone)]
+pub struct SolverOptions {
+    /// Known values of variables, which are not solved for
+    pub params: HashMap<String, Complex64>,
+    pub iterations: usize,
+    /// Starts tried before giving up, all but the first from random values
+    pub attempts: usize,
+    /// Values of unknowns by name for the first attempt. Others start from the guess of their
+    /// symbol, or at random like every unknown of later attempts.
+    pub initial: HashMap<String, Complex64>,
+    pub seed: u64,
+    pub branch: Branch,
+    /// Largest residual of a solution
+    pub tolerance: f64,
+    /// Follow a homotopy from the initial values instead of iterating from them directly
+    pub homotopy: bool,
+    /// Only accept solutions where every unknown is real, starting from real values
+    pub real_only: bool,
+    /// Polish the solution to about 32 significant digits, kept in `Solution::precise`
+    pub refine: bool,
+    /// Prove that a unique root lies close to the solution, giving it in `Solution::enclosure`
+    pub certify: bool,
+    /// Report how far the solution can be trusted in `Solution::verification`
+    pub verify: bool,
+    /// Where Newton iterations get the Jacobian from
+    pub method: Method,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        SolverOptions {
+            params: HashMap::new(),
+            iterations: 1000,
+            attempts: 50,
+            initial: HashMap::new(),
+            seed: 0,
+            branch: Branch::default(),
+            tolerance: TOLERANCE,
+            homotopy: false,
+            real_only: false,
+            refine: false,
+            certify: false,
+            verify: false,
+            method: Method::default(),
+        }
+    }
+}
+
+impl SolverOptions {
+    /// Starts the first attempt from a previous solution, such as one of the same system with
+    /// slightly different known values
+    pub fn warm_start(&mut self, previous: &Solution) {
+        self.initial
+            .extend(previous.iter().map(|(name, v)| (name.to_string(), v)))
+    }
+}
+
+/// Values of the unknowns of a system
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub names: Vec<String>,
+    pub values: Vec<Complex64>,
+    /// SI units of every value, `None` where the equations do not fix them
+    pub units: Vec<Option<Dimension>>,
+    /// The values to about 32 significant digits, if the solution was refined. `None` when
+    /// refining was not asked for or the Jacobian is singular at the solution.
+    pub precise: Option<Vec<ComplexDD>>,
+    /// Boxes around the values holding exactly one root of the system, if certifying was
+    /// asked for and succeeded
+    pub enclosure: Option<Vec<ComplexInterval>>,
+    /// Multiplicity of the root, as `System::multiplicity`. It is only worked out when
+    /// verifying or when the root looks multiple while solving, and is 1 otherwise.
+    pub multiplicity: usize,
+    /// Residual, conditioning and error estimates, if asked for
+    pub verification: Option<Verification>,
+    /// Jacobians computed by Newton iterations over every attempt, as `NewtonStats::jacobians`
+    pub jacobians: usize,
+    /// Directions in which the values can move and still solve the system, when it is linear
+    /// with infinitely many solutions. The values are then the solution of least norm. Empty
+    /// when the solution is isolated.
+    pub null_space: Vec<Vec<Complex64>>,
+}
+
+impl Solution {
+    pub fn get(&self, name: &str) -> Option<Complex64> {
+        let id = self.names.iter().position(|n| n == name)?;
+        Some(self.values[id])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Complex64)> {
+        self.names
+            .iter()
+            .map(String::as_str)
+            .zip(self.values.iter().copied())
+    }
+}
+
+/// A random value for `symbol`, which is real and within its bounds if it has any
+fn random_value(rng: &mut Rng, symbol: &Symbol, real: bool) -> Complex64 {
+    if let Some((lo, hi)) = symbol.bounds {
+        let (lo, hi) = match (lo.is_finite(), hi.is_finite()) {
+            (true, true) => (lo, hi),
+            (true, false) => (lo, lo + 2.0 * RANDOM_RANGE),
+            (false, true) => (hi - 2.0 * RANDOM_RANGE, hi),
+            (false, false) => (-RANDOM_RANGE, RANDOM_RANGE),
+        };
+        return rng.range(lo, hi).into();
+    }
+    let re = rng.range(-RANDOM_RANGE, RANDOM_RANGE);
+    let im = if real {
+        0.0
+    } else {
+        rng.range(-RANDOM_RANGE, RANDOM_RANGE)
+    };
+    Complex64::new(re, im)
+}
+
+fn is_real(values: &[Complex64]) -> bool {
+    values
+        .iter()
+        .all(|v| v.im.abs() <= IMAGINARY_TOLERANCE * (1.0 + v.re.abs()))
+}
+
+impl System {
+    /// Checks that every name of `initial` is a variable and every value is finite
+    pub(crate) fn check_initial(&self, initial: &HashMap<String, Complex64>) -> Result<(), Error> {
+        for (name, v) in initial {
+            if self.var_id(name).is_none() {
+                return Err(Error::UnknownVariable(name.clone()));
+            }
+            if !v.is_finite() {
+                return Err(Error::InvalidInput(format!(
+                    "The initial value of `{}` is not a number",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Starting values of every unknown, from `initial` by name, then from the guess of its
+    /// symbol and otherwise random. Names that are not unknowns are ignored.
+    pub(crate) fn start(
+        &self,
+        initial: &HashMap<String, Complex64>,
+        rng: &mut Rng,
+        real: bool,
+    ) -> Vec<Complex64> {
+        self.symbols
+            .iter()
+            .map(|s| {
+                let guess = initial.get(&s.name).copied().or(s.initial);
+                guess.unwrap_or_else(|| random_value(rng, s, real))
+            })
+            .collect()
+    }
+
+    fn random_start(&self, rng: &mut Rng, real: bool) -> Vec<Complex64> {
+        self.symbols
+            .iter()
+            .map(|s| random_value(rng, s, real))
+            .collect()
+    }
+
+    /// Fails on the first symbol other than `free` that is a parameter, as parameters need a
+    /// value before solving
+    pub(crate) fn check_parameters(&self, free: Option<usize>) -> Result<(), Error> {
+        let unbound = self
+            .symbols
+            .iter()
+            .enumerate()
+            .find(|(id, s)| s.kind == SymbolKind::Parameter && Some(*id) != free);
+        match unbound {
+            Some((_, s)) => Err(Error::InvalidInput(format!(
+                "No value given for the parameter `{}`",
+                s.name
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether every value is real and within the bounds of its symbol, if it has any
+    pub(crate) fn within_bounds(&self, values: &[Complex64]) -> bool {
+        self.symbols
+            .iter()
+            .zip(values)
+            .all(|(s, v)| match s.bounds {
+                Some((lo, hi)) => is_real(&[*v]) && (lo..=hi).contains(&v.re),
+                None => true,
+            })
+    }
+
+    /// Solves for every variable without a value in `options.params`, directly if the system
+    /// is linear and otherwise block by block from several starting points. Symbols that are
+    /// parameters need a value, and solutions outside the bounds of a symbol are passed over.
+    /// A linear system with infinitely many solutions gives one of them and the directions of
+    /// the others in `Solution::null_space`.
+    pub fn solve_with(&self, options: &SolverOptions) -> Result<Solution, Error> {
+        self.check_initial(&options.initial)?;
+        let mut bound = self.with_params(&options.params);
+        bound.check_parameters(None)?;
+        bound.branch = options.branch;
+        bound.tolerance = options.tolerance;
+        bound.method = options.method;
+        let mut system = bound.clone();
+        system.simplify();
+
+        let mut jacobians = 0;
+        let mut null_space = vec![];
+        let (values, multiple) = match system.solve_linear() {
+            Some(LinearSolution::Unique(sol)) if system.within_bounds(&sol) => (sol, false),
+            Some(LinearSolution::Infinite {
+                particular,
+                null_space: directions,
+            }) if system.within_bounds(&particular) => {
+                null_space = directions;
+                (particular, false)
+            }
+            Some(LinearSolution::Unique(_) | LinearSolution::Infinite { .. }) => {
+                return Err(Error::NoSolution)
+            }
+            Some(LinearSolution::None) => return Err(Error::Inconsistent),
+            _ => {
+                let mut rng = Rng::new(options.seed);
+                (0..options.attempts)
+                    .find_map(|attempt| {
+                        let initial = if attempt == 0 {
+                            system.start(&options.initial, &mut rng, options.real_only)
+                        } else {
+                            system.random_start(&mut rng, options.real_only)
+                        };
+                        let mut stats = NewtonStats::default();
+                        let iter = options.iterations;
+                        let sol = if options.homotopy {
+                            system.converge_homotopy(iter, initial.into_iter(), &mut stats)
+                        } else {
+                            system.converge_blocks(iter, initial.into_iter(), &mut stats)
+                        };
+                        jacobians += stats.jacobians;
+                        sol.filter(|sol| !options.real_only || is_real(sol))
+                            .filter(|sol| system.within_bounds(sol))
+                            .map(|sol| (sol, stats.multiple))
+                    })
+                    .ok_or(Error::NoSolution)?
+            }
+        };
+        let mut values = if options.real_only {
+            if !is_real(&values) {
+                return Err(Error::NoSolution);
+            }
+            values.iter().map(|v| v.re.into()).collect()
+        } else {
+            values
+        };
+        // simplifying folds constants in `f64`, so refine on the equations as written
+        let precise = options
+            .refine
+            .then(|| bound.refine(&values))
+            .flatten()
+            .map(|mut precise| {
+                if options.real_only {
+                    precise.iter_mut().for_each(|v| v.im = Default::default());
+                }
+                values = precise.iter().map(|v| v.to_complex()).collect();
+                precise
+            });
+        let enclosure = options.certify.then(|| bound.certify(&values)).flatten();
+        // probing the root costs a few decompositions of the Jacobian, so only where asked to
+        // or where solving saw it singular, and a root that is not isolated has none
+        let multiplicity = if null_space.is_empty() && (multiple || options.verify) {
+            bound.multiplicity(&values)
+        } else {
+            1
+        };
+        let verification = options.verify.then(|| match &precise {
+            Some(precise) => bound.precise_verification(precise, multiplicity),
+            None => bound.verification(&values, multiplicity),
+        });
+        Ok(Solution {
+            names: system.symbols.names(),
+            units: system.symbols.iter().map(|s| s.unit).collect(),
+            values,
+            precise,
+            enclosure,
+            multiplicity,
+            verification,
+            jacobians,
+            null_space,
+        })
+    }
+}
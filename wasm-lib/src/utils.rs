@@ -8,3 +8,30 @@ pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+/// Small PCG generator for initial guesses, seeded so runs can be reproduced
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng(seed.wrapping_add(1442695040888963407));
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.0;
+        self.0 = old
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    /// Uniform in `[from, to)`
+    pub fn range(&mut self, from: f64, to: f64) -> f64 {
+        let bits = ((self.next_u32() as u64) << 21) ^ ((self.next_u32() as u64) >> 11);
+        let unit = bits as f64 / (1u64 << 53) as f64;
+        from + (to - from) * unit
+    }
+}
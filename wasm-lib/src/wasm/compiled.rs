@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use num_complex::Complex64;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use super::{
+    from_js_or_default, get_eqs, label, named_values, to_js, NamedValue, PositionedError, Value,
+};
+use crate::{
+    equation::{running::Branch, system::System, verification::Verification},
+    Error, Method, Solution, SolverOptions,
+};
+
+/// `SolverOptions` as given from JS, each of which can be left out
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct JsSolverOptions {
+    params: HashMap<String, f64>,
+    iterations: usize,
+    attempts: usize,
+    /// Values of some unknowns by name
+    initial: HashMap<String, Value>,
+    /// Start from the last solution found, for the unknowns not in `initial`
+    warm_start: bool,
+    seed: u64,
+    real_roots: bool,
+    branch: i32,
+    tolerance: f64,
+    homotopy: bool,
+    real_only: bool,
+    /// Polish the values to about 32 significant digits
+    refine: bool,
+    /// Look for boxes around the values that hold exactly one root
+    certify: bool,
+    /// Report the residual, the condition number and how many digits of each value are right
+    verify: bool,
+    /// `"newton"`, `"good_broyden"` or `"bad_broyden"`
+    method: Method,
+}
+
+impl Default for JsSolverOptions {
+    fn default() -> Self {
+        let defaults = SolverOptions::default();
+        JsSolverOptions {
+            params: HashMap::new(),
+            iterations: defaults.iterations,
+            attempts: defaults.attempts,
+            initial: HashMap::new(),
+            warm_start: false,
+            seed: defaults.seed,
+            real_roots: defaults.branch.real_roots,
+            branch: defaults.branch.k,
+            tolerance: defaults.tolerance,
+            homotopy: defaults.homotopy,
+            real_only: defaults.real_only,
+            refine: defaults.refine,
+            certify: defaults.certify,
+            verify: defaults.verify,
+            method: defaults.method,
+        }
+    }
+}
+
+impl From<JsSolverOptions> for SolverOptions {
+    fn from(o: JsSolverOptions) -> Self {
+        SolverOptions {
+            params: o
+                .params
+                .into_iter()
+                .map(|(name, v)| (name, v.into()))
+                .collect(),
+            iterations: o.iterations,
+            attempts: o.attempts,
+            initial: o
+                .initial
+                .into_iter()
+                .map(|(name, v)| (name, v.into()))
+                .collect(),
+            seed: o.seed,
+            branch: Branch {
+                real_roots: o.real_roots,
+                k: o.branch,
+            },
+            tolerance: o.tolerance,
+            homotopy: o.homotopy,
+            real_only: o.real_only,
+            refine: o.refine,
+            certify: o.certify,
+            verify: o.verify,
+            method: o.method,
+        }
+    }
+}
+
+/// `Verification` as passed to JS
+#[derive(Serialize)]
+struct JsVerification {
+    residual: f64,
+    condition: f64,
+    errors: Vec<f64>,
+    /// Significant digits of every value that are right
+    digits: Vec<usize>,
+    multiple: bool,
+}
+
+/// `Solution` as passed to JS, with the parts that were not asked for left undefined
+#[derive(Serialize)]
+struct JsSolution {
+    solution: Vec<NamedValue>,
+    /// Real and imaginary parts of every refined value, as decimals with all their digits
+    precise: Option<Vec<(String, String)>>,
+    /// `[[re_lo, re_hi], [im_lo, im_hi]]` around every value
+    enclosure: Option<Vec<[(f64, f64); 2]>>,
+    multiplicity: usize,
+    verification: Option<JsVerification>,
+    /// Jacobians computed while solving
+    jacobians: usize,
+    /// Directions of the other solutions of a linear system that has infinitely many
+    null_space: Vec<Vec<NamedValue>>,
+}
+
+impl JsSolution {
+    fn new(labels: &[(String, String)], sol: &Solution) -> Self {
+        JsSolution {
+            solution: named_values(labels, &sol.values),
+            precise: sol.precise.as_ref().map(|precise| {
+                precise
+                    .iter()
+                    .map(|v| (v.re.to_string(), v.im.to_string()))
+                    .collect()
+            }),
+            enclosure: sol.enclosure.as_ref().map(|boxes| {
+                boxes
+                    .iter()
+                    .map(|b| [(b.re.lo, b.re.hi), (b.im.lo, b.im.hi)])
+                    .collect()
+            }),
+            multiplicity: sol.multiplicity,
+            verification: sol.verification.as_ref().map(|v| JsVerification {
+                residual: v.residual,
+                condition: v.condition,
+                errors: v.errors.clone(),
+                digits: sol
+                    .values
+                    .iter()
+                    .zip(&v.errors)
+                    .map(|(value, error)| Verification::digits(*value, *error))
+                    .collect(),
+                multiple: v.multiple,
+            }),
+            jacobians: sol.jacobians,
+            null_space: sol
+                .null_space
+                .iter()
+                .map(|v| named_values(labels, v))
+                .collect(),
+        }
+    }
+}
+
+/// Equations parsed once, to be solved and evaluated many times
+#[wasm_bindgen]
+pub struct CompiledSystem {
+    system: System,
+    /// Latest solution, for warm starts
+    last: Option<Solution>,
+}
+
+impl CompiledSystem {
+    /// Reads the real and imaginary parts of every variable
+    fn args(&self, values: &[f64]) -> Result<Vec<Complex64>, PositionedError> {
+        let symbols = &self.system.symbols;
+        if values.len() != 2 * symbols.len() {
+            return Err(Error::InvalidInput(format!(
+                "Expected {} values, the real and imaginary parts of {}",
+                2 * symbols.len(),
+                symbols.names().join(", ")
+            ))
+            .into());
+        }
+        Ok(values
+            .iter()
+            .tuples()
+            .map(|(a, b)| Complex64::new(*a, *b))
+            .collect())
+    }
+}
+
+#[wasm_bindgen]
+impl CompiledSystem {
+    #[wasm_bindgen(constructor)]
+    pub fn new(eqs: JsValue) -> Result<CompiledSystem, PositionedError> {
+        Ok(CompiledSystem {
+            system: get_eqs(eqs)?,
+            last: None,
+        })
+    }
+
+    /// Names of the variables, in the order `evaluate` and `jacobian` take them
+    pub fn variables(&self) -> Vec<JsValue> {
+        self.system
+            .symbols
+            .iter()
+            .map(|s| JsValue::from_str(&s.name))
+            .collect()
+    }
+
+    /// Solves for every variable without a value in `options.params`, returning the solution
+    /// and whatever `options` asked to know of it, or `undefined` if no solution is found
+    pub fn solve(&mut self, options: JsValue) -> Result<JsValue, PositionedError> {
+        let options: JsSolverOptions = from_js_or_default(options, "solver options")?;
+        let warm_start = options.warm_start;
+        let mut options = SolverOptions::from(options);
+        if let (true, Some(last)) = (warm_start, &self.last) {
+            let given = std::mem::take(&mut options.initial);
+            options.warm_start(last);
+            options.initial.extend(given);
+        }
+        match self.system.solve_with(&options) {
+            Ok(sol) => {
+                let labels: Vec<_> = sol
+                    .names
+                    .iter()
+                    .zip(&sol.units)
+                    .map(|(name, unit)| label(name, *unit))
+                    .collect();
+                let out = to_js(&JsSolution::new(&labels, &sol));
+                self.last = Some(sol);
+                out
+            }
+            Err(Error::Inconsistent | Error::NoSolution) => Ok(JsValue::UNDEFINED),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// `left - right` of every equation at the given real and imaginary parts of the variables,
+    /// on the principal branch
+    pub fn evaluate(&self, values: &[f64]) -> Result<Vec<JsValue>, PositionedError> {
+        let args = self.args(values)?;
+        self.system
+            .run(&args)
+            .iter()
+            .map(|v| to_js(&(v.re, v.im)))
+            .collect()
+    }
+
+    /// Rows of the Jacobian of `evaluate`, one per equation
+    pub fn jacobian(&self, values: &[f64]) -> Result<Vec<JsValue>, PositionedError> {
+        let args = self.args(values)?;
+        self.system
+            .jacobian(&args)
+            .row_iter()
+            .map(|row| {
+                let row: Vec<(f64, f64)> = row.iter().map(|v| (v.re, v.im)).collect();
+                to_js(&row)
+            })
+            .collect()
+    }
+}
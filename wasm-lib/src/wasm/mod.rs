@@ -0,0 +1,593 @@
+mod compiled;
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use num_complex::Complex64;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    equation::{linear::LinearSolution, running::Branch, structure::Diagnostic, system::System},
+    error::Side,
+    parse_system,
+    parsing::{ast::ExprNode, symbols::SymbolTable, units::Dimension},
+    utils::Rng,
+    Error, SolverOptions,
+};
+
+pub use compiled::CompiledSystem;
+
+#[wasm_bindgen]
+extern "C" {
+    fn alert(s: &str);
+
+    #[wasm_bindgen(js_namespace = console)]
+    fn log(s: &str);
+
+    #[wasm_bindgen(js_namespace = Math)]
+    fn random() -> f64;
+}
+
+/// Names of the variables in the order they first appear, which is also the order of every
+/// solution
+#[wasm_bindgen]
+pub fn variables(eqs: JsValue) -> Result<Vec<JsValue>, PositionedError> {
+    let system = get_eqs(eqs)?;
+    Ok(system
+        .symbols
+        .iter()
+        .map(|s| JsValue::from_str(&s.name))
+        .collect())
+}
+
+/// Solves the system from one starting point, given as `{ name: value }` for some of the
+/// unknowns and random for the rest
+#[wasm_bindgen]
+pub fn solve(
+    eqs: JsValue,
+    params: JsValue,
+    iter: usize,
+    initial: JsValue,
+    real_roots: bool,
+    branch: i32,
+) -> Result<Option<Vec<JsValue>>, PositionedError> {
+    solve_once(eqs, params, iter, initial, real_roots, branch, false)
+}
+
+/// Like `solve`, but follows a homotopy from the initial values to the solution
+#[wasm_bindgen]
+pub fn solve_homotopy(
+    eqs: JsValue,
+    params: JsValue,
+    iter: usize,
+    initial: JsValue,
+    real_roots: bool,
+    branch: i32,
+) -> Result<Option<Vec<JsValue>>, PositionedError> {
+    solve_once(eqs, params, iter, initial, real_roots, branch, true)
+}
+
+/// One attempt of `System::solve_with`, which solves linear systems directly and checks
+/// parameters and bounds
+fn solve_once(
+    eqs: JsValue,
+    params: JsValue,
+    iter: usize,
+    initial: JsValue,
+    real_roots: bool,
+    branch: i32,
+    homotopy: bool,
+) -> Result<Option<Vec<JsValue>>, PositionedError> {
+    let system = get_eqs(eqs)?;
+    let options = SolverOptions {
+        params: read_params(params)?,
+        iterations: iter,
+        attempts: 1,
+        initial: read_initial(&system, initial)?,
+        seed: js_seed(),
+        branch: Branch {
+            real_roots,
+            k: branch,
+        },
+        homotopy,
+        ..SolverOptions::default()
+    };
+    match system.solve_with(&options) {
+        Ok(sol) => {
+            let names: Vec<_> = sol
+                .names
+                .iter()
+                .zip(&sol.units)
+                .map(|(name, unit)| label(name, *unit))
+                .collect();
+            named_values(&names, &sol.values)
+                .iter()
+                .map(to_js)
+                .collect::<Result<_, _>>()
+                .map(Some)
+        }
+        Err(Error::Inconsistent | Error::NoSolution) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[derive(Serialize)]
+struct SweepPoint {
+    param: f64,
+    solution: Option<Vec<NamedValue>>,
+}
+
+/// Solves the system for `steps + 1` evenly spaced values of `param` from `from` to `to`,
+/// continuing each solution from the previous one
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn sweep(
+    eqs: JsValue,
+    params: JsValue,
+    param: String,
+    from: f64,
+    to: f64,
+    steps: usize,
+    iter: usize,
+    initial: JsValue,
+    real_roots: bool,
+    branch: i32,
+) -> Result<Vec<JsValue>, PositionedError> {
+    let system = get_eqs(eqs)?;
+    let initial = read_initial(&system, initial)?;
+    let mut system = bind_params(&system, params)?;
+    let mut names = labels(&system.symbols);
+    system.branch = Branch {
+        real_roots,
+        k: branch,
+    };
+    system.simplify();
+
+    let Some(param_id) = system.var_id(&param) else {
+        return Err(Error::UnknownVariable(param).into());
+    };
+    system.check_parameters(Some(param_id))?;
+    names.remove(param_id);
+
+    let values: Vec<f64> = (0..=steps)
+        .map(|i| from + (to - from) * i as f64 / steps.max(1) as f64)
+        .collect();
+    let initial = system.start(&initial, &mut js_rng(), false);
+
+    let curves = system.sweep(
+        param_id,
+        &values
+            .iter()
+            .map(|v| Complex64::from(*v))
+            .collect::<Vec<_>>(),
+        initial,
+        iter,
+    );
+    values
+        .iter()
+        .zip(curves)
+        .map(|(param, sol)| {
+            // the bounds are on every variable, the swept one included
+            let sol = sol.filter(|sol| {
+                let mut values = sol.clone();
+                values.insert(param_id, (*param).into());
+                system.within_bounds(&values)
+            });
+            to_js(&SweepPoint {
+                param: *param,
+                solution: sol.map(|sol| named_values(&names, &sol)),
+            })
+        })
+        .collect()
+}
+
+/// `[name, [re, im], unit]`, the unit being empty if there is none or it is unknown
+type NamedValue = (String, (f64, f64), String);
+
+fn label(name: &str, unit: Option<Dimension>) -> (String, String) {
+    let unit = unit.filter(|u| !u.is_none());
+    (
+        name.to_string(),
+        unit.map(|u| u.to_string()).unwrap_or_default(),
+    )
+}
+
+/// Name and unit of every symbol
+fn labels(symbols: &SymbolTable) -> Vec<(String, String)> {
+    symbols.iter().map(|s| label(&s.name, s.unit)).collect()
+}
+
+fn named_values(labels: &[(String, String)], values: &[Complex64]) -> Vec<NamedValue> {
+    labels
+        .iter()
+        .zip(values)
+        .map(|((name, unit), v)| (name.clone(), (v.re, v.im), unit.clone()))
+        .collect()
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum LinearResult {
+    Unique {
+        solution: Vec<NamedValue>,
+    },
+    None,
+    Infinite {
+        solution: Vec<NamedValue>,
+        null_space: Vec<Vec<NamedValue>>,
+    },
+}
+
+/// Solves the system directly if every equation is linear, otherwise returns `undefined`
+#[wasm_bindgen]
+pub fn solve_linear(
+    eqs: JsValue,
+    params: JsValue,
+    real_roots: bool,
+    branch: i32,
+) -> Result<JsValue, PositionedError> {
+    let mut system = bind_params(&get_eqs(eqs)?, params)?;
+    let names = labels(&system.symbols);
+    system.branch = Branch {
+        real_roots,
+        k: branch,
+    };
+    system.simplify();
+
+    let result = match system.solve_linear() {
+        None => return Ok(JsValue::UNDEFINED),
+        Some(LinearSolution::Unique(sol)) => LinearResult::Unique {
+            solution: named_values(&names, &sol),
+        },
+        Some(LinearSolution::None) => LinearResult::None,
+        Some(LinearSolution::Infinite {
+            particular,
+            null_space,
+        }) => LinearResult::Infinite {
+            solution: named_values(&names, &particular),
+            null_space: null_space.iter().map(|v| named_values(&names, v)).collect(),
+        },
+    };
+    to_js(&result)
+}
+
+#[derive(Serialize)]
+struct Root {
+    solution: Vec<NamedValue>,
+    residual: f64,
+    newton_step: f64,
+    condition: f64,
+    regular: bool,
+    paths: usize,
+}
+
+#[derive(Serialize)]
+struct AllRoots {
+    roots: Vec<Root>,
+    paths: usize,
+    lost: usize,
+}
+
+/// Finds every isolated solution of a square polynomial system, with evidence for each one,
+/// otherwise returns `undefined`
+#[wasm_bindgen]
+pub fn solve_all(
+    eqs: JsValue,
+    params: JsValue,
+    iter: usize,
+    real_roots: bool,
+    branch: i32,
+) -> Result<JsValue, PositionedError> {
+    let mut system = bind_params(&get_eqs(eqs)?, params)?;
+    let names = labels(&system.symbols);
+    system.branch = Branch {
+        real_roots,
+        k: branch,
+    };
+    system.simplify();
+
+    let Some(solutions) = system.solve_polynomial(iter) else {
+        return Ok(JsValue::UNDEFINED);
+    };
+    let result = AllRoots {
+        roots: solutions
+            .roots
+            .into_iter()
+            .map(|root| Root {
+                solution: named_values(&names, &root.solution),
+                residual: root.certificate.residual,
+                newton_step: root.certificate.newton_step,
+                condition: root.certificate.condition,
+                regular: root.certificate.is_regular(),
+                paths: root.paths,
+            })
+            .collect(),
+        paths: solutions.paths,
+        lost: solutions.lost,
+    };
+    to_js(&result)
+}
+
+/// A real number or the real and imaginary parts of a complex one
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Value {
+    Real(f64),
+    Complex(f64, f64),
+}
+
+impl From<Value> for Complex64 {
+    fn from(v: Value) -> Self {
+        match v {
+            Value::Real(re) => re.into(),
+            Value::Complex(re, im) => Complex64::new(re, im),
+        }
+    }
+}
+
+/// Computes a single expression, with the values of its variables given as `{ name: value }`,
+/// returning its real and imaginary parts
+#[wasm_bindgen]
+pub fn evaluate(
+    expr: &str,
+    bindings: JsValue,
+    real_roots: bool,
+    branch: i32,
+) -> Result<JsValue, PositionedError> {
+    let bindings: HashMap<String, Value> = from_js_or_default(bindings, "bindings")?;
+    let bindings = bindings
+        .into_iter()
+        .map(|(name, v)| (name, v.into()))
+        .collect();
+    let branch = Branch {
+        real_roots,
+        k: branch,
+    };
+    let v = crate::evaluate(expr, &bindings, branch)?;
+    to_js(&(v.re, v.im))
+}
+
+#[derive(Serialize)]
+struct EquationPreview {
+    text: String,
+    latex: String,
+}
+
+/// Formats every equation the way it was parsed, as source and as LaTeX
+#[wasm_bindgen]
+pub fn preview(eqs: JsValue) -> Result<Vec<JsValue>, PositionedError> {
+    get_eqs(eqs)?
+        .preview()
+        .into_iter()
+        .map(|(text, latex)| to_js(&EquationPreview { text, latex }))
+        .collect()
+}
+
+/// Rearranges equation `eq` into a formula for `var`
+#[wasm_bindgen]
+pub fn isolate(
+    eqs: JsValue,
+    eq: usize,
+    var: String,
+    real_roots: bool,
+    branch: i32,
+) -> Result<JsValue, PositionedError> {
+    let mut system = get_eqs(eqs)?;
+    system.branch = Branch {
+        real_roots,
+        k: branch,
+    };
+    let formula = system.isolate(eq, &var).map_err(|e| match e {
+        Error::UnknownVariable(_) => PositionedError::at(eq, "unknown_variable", e.to_string()),
+        e => e.into(),
+    })?;
+
+    let symbols = &system.symbols;
+    let var = ExprNode::Var(symbols.id(&var).unwrap());
+    let preview = EquationPreview {
+        text: format!("{} = {}", var.display(symbols), formula.display(symbols)),
+        latex: format!("{} = {}", var.latex(symbols), formula.latex(symbols)),
+    };
+    to_js(&preview)
+}
+
+#[derive(Serialize)]
+struct Warning {
+    kind: &'static str,
+    msg: String,
+    eqs: Vec<usize>,
+    vars: Vec<String>,
+}
+
+/// Structural problems of the system, like equations that cannot all hold or variables that
+/// are left free
+#[wasm_bindgen]
+pub fn analyze(
+    eqs: JsValue,
+    params: JsValue,
+    real_roots: bool,
+    branch: i32,
+) -> Result<Vec<JsValue>, PositionedError> {
+    let mut system = bind_params(&get_eqs(eqs)?, params)?;
+    let names = labels(&system.symbols);
+    system.branch = Branch {
+        real_roots,
+        k: branch,
+    };
+    system.simplify();
+
+    let var_names = |vars: &[usize]| vars.iter().map(|v| names[*v].0.clone()).collect::<Vec<_>>();
+    // counted from 0, like `Warning::eqs` and `PositionedError::eq`
+    let eq_list = |eqs: &[usize]| eqs.iter().join(", ");
+
+    system
+        .analyze()
+        .into_iter()
+        .map(|d| match d {
+            Diagnostic::CountMismatch { eqs, vars } => Warning {
+                kind: "count_mismatch",
+                msg: format!("There are {} equations for {} variables", eqs, vars),
+                eqs: vec![],
+                vars: vec![],
+            },
+            Diagnostic::UnusedVar(var) => Warning {
+                kind: "unused_var",
+                msg: format!("`{}` does not appear in any equation", names[var].0),
+                eqs: vec![],
+                vars: var_names(&[var]),
+            },
+            Diagnostic::Overdetermined { eqs, surplus } => Warning {
+                kind: "overdetermined",
+                msg: format!(
+                    "{} of equations {} are redundant or contradict the others",
+                    surplus,
+                    eq_list(&eqs)
+                ),
+                vars: vec![],
+                eqs,
+            },
+            Diagnostic::Underdetermined { vars, surplus } => Warning {
+                kind: "underdetermined",
+                msg: format!(
+                    "{} of {} are not determined by the equations",
+                    surplus,
+                    var_names(&vars).join(", ")
+                ),
+                eqs: vec![],
+                vars: var_names(&vars),
+            },
+        })
+        .map(|w| to_js(&w))
+        .collect()
+}
+
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct PositionedError {
+    msg: String,
+    kind: &'static str,
+    pub eq: usize,
+    pub second: bool,
+}
+#[wasm_bindgen]
+impl PositionedError {
+    #[wasm_bindgen(getter = msg)]
+    pub fn msg(&self) -> String {
+        self.msg.clone()
+    }
+
+    /// What went wrong, `parse`, `units`, `invalid_input`, `unknown_variable`,
+    /// `not_isolatable`, `inconsistent` or `no_solution`
+    #[wasm_bindgen(getter = kind)]
+    pub fn kind(&self) -> String {
+        self.kind.to_string()
+    }
+}
+
+impl PositionedError {
+    fn at(eq: usize, kind: &'static str, msg: String) -> Self {
+        PositionedError {
+            msg,
+            kind,
+            eq,
+            second: false,
+        }
+    }
+}
+
+impl From<Error> for PositionedError {
+    fn from(e: Error) -> Self {
+        let kind = match e {
+            Error::Parse { .. } => "parse",
+            Error::Units { .. } => "units",
+            Error::InvalidInput(_) => "invalid_input",
+            Error::UnknownVariable(_) => "unknown_variable",
+            Error::NotIsolatable { .. } => "not_isolatable",
+            Error::Inconsistent => "inconsistent",
+            Error::NoSolution => "no_solution",
+        };
+        match e {
+            Error::Parse { eq, side, msg } | Error::Units { eq, side, msg } => PositionedError {
+                msg,
+                kind,
+                eq,
+                second: side == Side::Right,
+            },
+            Error::NotIsolatable { eq, msg } => PositionedError::at(eq, kind, msg),
+            e => PositionedError {
+                msg: e.to_string(),
+                kind,
+                eq: 0,
+                second: false,
+            },
+        }
+    }
+}
+
+/// Reads a value passed from JS, `what` naming it in the error if it has the wrong shape
+fn from_js<T: DeserializeOwned>(v: JsValue, what: &str) -> Result<T, PositionedError> {
+    serde_wasm_bindgen::from_value(v)
+        .map_err(|e| Error::InvalidInput(format!("Invalid {}: {}", what, e)).into())
+}
+
+/// Like `from_js`, but `undefined` and `null` give the default value
+fn from_js_or_default<T: DeserializeOwned + Default>(
+    v: JsValue,
+    what: &str,
+) -> Result<T, PositionedError> {
+    if v.is_undefined() || v.is_null() {
+        Ok(T::default())
+    } else {
+        from_js(v, what)
+    }
+}
+
+fn to_js<T: Serialize + ?Sized>(v: &T) -> Result<JsValue, PositionedError> {
+    serde_wasm_bindgen::to_value(v)
+        .map_err(|e| Error::InvalidInput(format!("Could not pass result to JS: {}", e)).into())
+}
+
+/// Reads initial values given as `{ name: value }` for variables of `system`
+fn read_initial(
+    system: &System,
+    initial: JsValue,
+) -> Result<HashMap<String, Complex64>, PositionedError> {
+    let initial: HashMap<String, Value> = from_js_or_default(initial, "initial values")?;
+    let initial = initial
+        .into_iter()
+        .map(|(name, v)| (name, v.into()))
+        .collect();
+    system.check_initial(&initial)?;
+    Ok(initial)
+}
+
+/// Seed for the values that are not given, from `Math.random`
+fn js_seed() -> u64 {
+    (random() * (1u64 << 53) as f64) as u64
+}
+
+/// Generator for the values that are not given, seeded from `Math.random`
+fn js_rng() -> Rng {
+    Rng::new(js_seed())
+}
+
+/// Known values given as `{ name: value }`
+fn read_params(params: JsValue) -> Result<HashMap<String, Complex64>, PositionedError> {
+    let params: HashMap<String, f64> = from_js_or_default(params, "known values")?;
+    Ok(params
+        .into_iter()
+        .map(|(name, v)| (name, v.into()))
+        .collect())
+}
+
+/// Binds the known values given as `{ name: value }`, returning the system of the remaining
+/// unknowns. Names that do not appear in the equations are ignored.
+fn bind_params(system: &System, params: JsValue) -> Result<System, PositionedError> {
+    Ok(system.with_params(&read_params(params)?))
+}
+
+fn get_eqs(eqs: JsValue) -> Result<System, PositionedError> {
+    let eqs: Vec<(String, String)> = from_js(eqs, "equations")?;
+    Ok(parse_system(&eqs)?)
+}
@@ -0,0 +1,60 @@
+//! The Rust API, which does not need the wasm bindings
+
+use std::collections::HashMap;
+
+use num_complex::Complex64;
+use wasm_lib::{parse_system, Error, SolverOptions};
+
+#[test]
+fn solve_by_name() {
+    let system = parse_system(&[("x + y", "3"), ("x * y", "2")]).unwrap();
+    let options = SolverOptions {
+        initial: HashMap::from([("x".to_string(), Complex64::from(0.8))]),
+        ..SolverOptions::default()
+    };
+    let solution = system.solve_with(&options).unwrap();
+    assert_eq!(solution.names, ["x", "y"]);
+    let x = solution.get("x").unwrap();
+    assert!((x - 1.0).norm() < 1e-9, "{}", x);
+    assert!(solution.get("z").is_none());
+    let named: Vec<&str> = solution.iter().map(|(name, _)| name).collect();
+    assert_eq!(named, ["x", "y"]);
+    assert!(system.run(&solution.values).norm() < 1e-8);
+}
+
+#[test]
+fn warm_start_follows_the_root() {
+    let system = parse_system(&[("x^2", "a")]).unwrap();
+    let mut options = SolverOptions {
+        params: HashMap::from([("a".to_string(), Complex64::from(4.0))]),
+        initial: HashMap::from([("x".to_string(), Complex64::from(-1.0))]),
+        ..SolverOptions::default()
+    };
+    let mut last = system.solve_with(&options).unwrap();
+    for a in [5.0, 6.0, 7.0] {
+        options.params.insert("a".to_string(), a.into());
+        options.initial.clear();
+        options.warm_start(&last);
+        last = system.solve_with(&options).unwrap();
+        let x = last.get("x").unwrap();
+        assert!((x + a.sqrt()).norm() < 1e-9, "{} for a = {}", x, a);
+    }
+}
+
+#[test]
+fn errors_are_std_errors() {
+    let err: Box<dyn std::error::Error> = parse_system(&[("x +", "1")]).unwrap_err().into();
+    assert!(!err.to_string().is_empty());
+
+    let system = parse_system(&[("x", "x + 1")]).unwrap();
+    let err = system.solve_with(&SolverOptions::default()).unwrap_err();
+    assert_eq!(err, Error::Inconsistent);
+    assert_eq!(err.to_string(), "The equations have no solution");
+
+    let options = SolverOptions {
+        initial: HashMap::from([("y".to_string(), Complex64::from(1.0))]),
+        ..SolverOptions::default()
+    };
+    let err = system.solve_with(&options).unwrap_err();
+    assert_eq!(err, Error::UnknownVariable("y".to_string()));
+}
@@ -0,0 +1,92 @@
+//! Branches of multivalued functions and the operators they are written with
+
+use std::f64::consts::PI;
+
+use num_complex::Complex64;
+use wasm_lib::{parse_system, Branch};
+
+/// The value of `expr` at `x`
+fn value(expr: &str, x: f64, branch: Branch) -> Complex64 {
+    let system = parse_system(&[("x", expr)]).unwrap();
+    system.eqs[0].right.run(&[Complex64::from(x)], branch)
+}
+
+fn close(a: Complex64, b: Complex64) -> bool {
+    (a - b).norm() < 1e-12
+}
+
+fn branch(k: i32) -> Branch {
+    Branch {
+        real_roots: false,
+        k,
+    }
+}
+
+#[test]
+fn branch_index_selects_the_branch() {
+    assert!(close(
+        value("ln(x)", -1.0, branch(0)),
+        Complex64::new(0.0, PI)
+    ));
+    assert!(close(
+        value("ln(x)", -1.0, branch(1)),
+        Complex64::new(0.0, 3.0 * PI)
+    ));
+    assert!(close(
+        value("ln(x)", -1.0, branch(-1)),
+        Complex64::new(0.0, -PI)
+    ));
+    assert!(close(value("sqrt(x)", 4.0, branch(1)), (-2.0).into()));
+    assert!(close(value("x^(1/2)", 4.0, branch(1)), (-2.0).into()));
+    assert!(close(value("x^(1/2)", 4.0, branch(2)), 2.0.into()));
+    assert!(close(value("asin(x)", 0.0, branch(1)), PI.into()));
+    assert!(close(value("atan(x)", 0.0, branch(-1)), (-PI).into()));
+
+    let cube_roots = (0..3).map(|k| value("cbrt(x)", 8.0, branch(k)));
+    for (k, root) in cube_roots.enumerate() {
+        assert!(close(root.powi(3), 8.0.into()), "k = {}", k);
+        assert!(close(
+            root,
+            Complex64::from_polar(2.0, 2.0 * PI * k as f64 / 3.0)
+        ));
+    }
+}
+
+#[test]
+fn real_roots_of_negative_numbers() {
+    let real = Branch {
+        real_roots: true,
+        k: 0,
+    };
+    assert!(close(value("x^(1/3)", -8.0, real), (-2.0).into()));
+    assert!(close(value("x^(2/3)", -8.0, real), 4.0.into()));
+    assert!(close(value("x^(1/5)", -32.0, real), (-2.0).into()));
+    assert!(close(value("cbrt(x)", -8.0, real), (-2.0).into()));
+    // even roots have no real value, so they stay complex
+    assert!(close(
+        value("x^(1/2)", -4.0, real),
+        Complex64::new(0.0, 2.0)
+    ));
+
+    let principal = value("x^(1/3)", -8.0, Branch::default());
+    assert!(close(principal, Complex64::new(1.0, 3f64.sqrt())));
+
+    // real roots have no other branches
+    let other = Branch { k: 1, ..real };
+    assert!(close(value("cbrt(x)", -8.0, other), (-2.0).into()));
+    assert!(close(value("cbrt(x)", 8.0, other), 2.0.into()));
+}
+
+#[test]
+fn unary_operators() {
+    let b = Branch::default();
+    assert!(close(value("-x^2", 3.0, b), (-9.0).into()));
+    assert!(close(value("(-x)^2", 3.0, b), 9.0.into()));
+    assert!(close(value("2^-x", 1.0, b), 0.5.into()));
+    assert!(close(value("2 * -x", 3.0, b), (-6.0).into()));
+    assert!(close(value("1 - -x", 3.0, b), 4.0.into()));
+    assert!(close(value("--x", 3.0, b), 3.0.into()));
+    assert!(close(value("+x", 3.0, b), 3.0.into()));
+    assert!(close(value("(-8)", 0.0, b), (-8.0).into()));
+    assert!(close(value("-x + 1", 3.0, b), (-2.0).into()));
+}
@@ -0,0 +1,106 @@
+//! Broyden's methods find the same solutions as Newton's
+
+use std::collections::HashMap;
+
+use num_complex::Complex64;
+use wasm_lib::{parse_system, Method, SolverOptions};
+
+const METHODS: [Method; 3] = [Method::Newton, Method::GoodBroyden, Method::BadBroyden];
+
+fn residual(eqs: &[(&str, &str)], method: Method) -> f64 {
+    let system = parse_system(eqs).unwrap();
+    let options = SolverOptions {
+        method,
+        ..SolverOptions::default()
+    };
+    let solution = system.solve_with(&options).unwrap();
+    system.run(&solution.values).norm()
+}
+
+#[test]
+fn broyden_solves_nonlinear_systems() {
+    let systems = [
+        vec![("x^2", "2")],
+        vec![("x + y", "3"), ("x * y", "1")],
+        vec![("x^2 + y^2", "4"), ("e^x", "y + 1")],
+        vec![
+            ("sin(x) + y", "1"),
+            ("x - cos(y)", "0"),
+            ("x + y + z", "z^3"),
+        ],
+    ];
+    for eqs in &systems {
+        for method in METHODS {
+            assert!(residual(eqs, method) < 1e-6, "{:?} with {:?}", eqs, method);
+        }
+    }
+}
+
+#[test]
+fn broyden_solves_chained_systems() {
+    // every equation holds two neighbouring unknowns, so no block can be split off
+    let eqs: Vec<(String, String)> = (0..8)
+        .map(|i| (format!("x{}^2 + x{}", i, (i + 1) % 8), format!("{}", i + 2)))
+        .collect();
+    let system = parse_system(&eqs).unwrap();
+    for method in METHODS {
+        let options = SolverOptions {
+            method,
+            ..SolverOptions::default()
+        };
+        let solution = system.solve_with(&options).unwrap();
+        assert!(system.run(&solution.values).norm() < 1e-6, "{:?}", method);
+    }
+}
+
+#[test]
+fn broyden_converges_to_multiple_roots() {
+    for method in [Method::GoodBroyden, Method::BadBroyden] {
+        let options = SolverOptions {
+            method,
+            tolerance: 1e-20,
+            ..SolverOptions::default()
+        };
+        let solution = parse_system(&[("(x - 1)^2", "0")])
+            .unwrap()
+            .solve_with(&options)
+            .unwrap();
+        assert!((solution.values[0] - Complex64::new(1.0, 0.0)).norm() < 1e-9);
+        assert_eq!(solution.multiplicity, 2);
+    }
+}
+
+#[test]
+fn broyden_computes_fewer_jacobians() {
+    // one block of 60 unknowns, each equation holding two neighbouring ones
+    let n = 60;
+    let eqs: Vec<(String, String)> = (0..n)
+        .map(|i| {
+            (
+                format!("x{}^2 + x{}", i, (i + 1) % n),
+                format!("{}", i % 5 + 2),
+            )
+        })
+        .collect();
+    let system = parse_system(&eqs).unwrap();
+    let root = system.solve_with(&SolverOptions::default()).unwrap();
+    // close enough to the root that every method converges without a fresh start
+    let initial: HashMap<String, Complex64> = root
+        .iter()
+        .map(|(name, v)| (name.to_string(), v * 1.2))
+        .collect();
+
+    let jacobians = METHODS.map(|method| {
+        let options = SolverOptions {
+            method,
+            initial: initial.clone(),
+            ..SolverOptions::default()
+        };
+        let solution = system.solve_with(&options).unwrap();
+        assert!(system.run(&solution.values).norm() < 1e-6, "{:?}", method);
+        solution.jacobians
+    });
+    // Newton's method takes a Jacobian every step, while Broyden's methods update their first
+    let [newton, good, bad] = jacobians;
+    assert!(good * 3 <= newton && bad * 3 <= newton, "{:?}", jacobians);
+}
@@ -0,0 +1,52 @@
+//! Certified solutions come with boxes proven to hold exactly one root
+
+use num_complex::Complex64;
+use wasm_lib::{equation::interval::ComplexInterval, parse_system, SolverOptions};
+
+fn certified(eqs: &[(&str, &str)], options: SolverOptions) -> Option<Vec<ComplexInterval>> {
+    let options = SolverOptions {
+        certify: true,
+        ..options
+    };
+    let solution = parse_system(eqs).unwrap().solve_with(&options).unwrap();
+    solution.enclosure
+}
+
+/// Whether the box holds `v` and is small
+fn holds(b: &ComplexInterval, v: Complex64) -> bool {
+    let tight = b.re.hi - b.re.lo < 1e-10 && b.im.hi - b.im.lo < 1e-10;
+    tight && b.re.contains(v.re) && b.im.contains(v.im)
+}
+
+#[test]
+fn boxes_hold_the_root() {
+    let boxes = certified(&[("x^2", "2")], SolverOptions::default()).unwrap();
+    assert!(holds(&boxes[0], 2f64.sqrt().into()) || holds(&boxes[0], (-2f64.sqrt()).into()));
+
+    let boxes = certified(&[("x^2", "-9"), ("y", "e^x")], SolverOptions::default()).unwrap();
+    let x = if boxes[0].im.lo > 0.0 { 3.0 } else { -3.0 };
+    assert!(holds(&boxes[0], Complex64::new(0.0, x)));
+    assert!(holds(&boxes[1], Complex64::new(0.0, x).exp()));
+}
+
+#[test]
+fn real_roots_get_real_boxes() {
+    let mut system = parse_system(&[("cbrt(x) + x", "-10")]).unwrap();
+    system.branch.real_roots = true;
+    let boxes = system.certify(&[(-8.0).into()]).unwrap();
+    assert!(holds(&boxes[0], (-8.0).into()));
+    assert_eq!((boxes[0].im.lo, boxes[0].im.hi), (0.0, 0.0));
+    assert_eq!(system.certify(&[Complex64::new(-8.0, 1.0)]), None);
+}
+
+#[test]
+fn singular_roots_are_not_certified() {
+    assert_eq!(
+        certified(&[("(x - 1)^2", "0")], SolverOptions::default()),
+        None
+    );
+    let line = parse_system(&[("x + y", "2"), ("2x", "4 - 2y")]).unwrap();
+    assert_eq!(line.certify(&[1.0.into(), 1.0.into()]), None);
+    let overdetermined = parse_system(&[("x", "2"), ("x^2", "4")]).unwrap();
+    assert_eq!(overdetermined.certify(&[2.0.into()]), None);
+}
@@ -0,0 +1,172 @@
+//! The command line solver's output formats and exit codes
+
+#![cfg(feature = "cli")]
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+/// Runs the solver on `input` given on stdin
+fn solver(input: &str, args: &[&str]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_equation-solver"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // the solver exits without reading stdin when the arguments are wrong
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+const SYSTEM: &str = "# a line and a circle\nx + y = 3\n\nx - y = 1\n";
+
+#[test]
+fn text() {
+    assert_eq!(stdout(&solver(SYSTEM, &[])), "x = 2\ny = 1\n");
+    let output = solver("F = m * a", &["-p", "m=2", "--param", "a = 3"]);
+    assert_eq!(stdout(&output), "F = 6\n");
+    let output = solver("d = v * t\nv = 3 [m/s]\nt = 2 [s]", &[]);
+    assert_eq!(stdout(&output), "d = 6 m\nv = 3 m/s\nt = 2 s\n");
+
+    let path = std::env::temp_dir().join("equation-solver-cli-test.txt");
+    std::fs::write(&path, SYSTEM).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_equation-solver"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(stdout(&output), "x = 2\ny = 1\n");
+}
+
+#[test]
+fn csv() {
+    let output = solver("x + y = 3 [m]\nx - y = 1 [m]", &["-f", "csv"]);
+    assert_eq!(stdout(&output), "variable,re,im,unit\nx,2,0,m\ny,1,0,m\n");
+}
+
+#[test]
+fn json() {
+    let output = solver(SYSTEM, &["--format", "json"]);
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    let expected = serde_json::json!([
+        { "name": "x", "value": [2.0, 0.0], "unit": null },
+        { "name": "y", "value": [1.0, 0.0], "unit": null },
+    ]);
+    assert_eq!(json, expected);
+}
+
+#[test]
+fn json_keeps_the_order_of_the_variables() {
+    let output = solver("F = m*a", &["-f", "json", "-p", "m=2", "-p", "a=3"]);
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    let expected = serde_json::json!([{ "name": "F", "value": [6.0, 0.0], "unit": null }]);
+    assert_eq!(json, expected);
+    let output = solver("F = m*a\nm = 2\na = 3", &["-f", "json"]);
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    let names: Vec<&str> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["F", "m", "a"]);
+
+    let output = solver("z = 1\nb = 2\na = 3", &["-f", "json", "--refine"]);
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(json[0]["name"], "z");
+    assert_eq!(json[2]["name"], "a");
+    assert!(json[2]["value"][0].as_str().unwrap().starts_with('3'));
+}
+
+#[test]
+fn notes_go_to_stderr_unless_text() {
+    let output = solver("(x - 1)^2 = 0", &["-f", "csv"]);
+    assert!(stdout(&output).starts_with("variable,re,im,unit\nx,"));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("multiplicity 2"), "{}", stderr);
+}
+
+#[test]
+fn infinitely_many_solutions() {
+    let output = solver("x + y = 2", &[]);
+    let stdout = stdout(&output);
+    assert!(
+        stdout.starts_with("x = 1\ny = 1\ninfinitely many solutions"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.ends_with("x = 0.707107, y = -0.707107\n")
+            || stdout.ends_with("x = -0.707107, y = 0.707107\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn exit_codes() {
+    for (input, error) in [
+        ("x = x + 1", "The equations have no solution"),
+        ("x^2 = -1", "No solutions found"),
+        ("x = (1", "line 1, right side:"),
+        ("\n# x\nx + 1", "line 3: expected `left = right`"),
+    ] {
+        let output = solver(input, &["--real-only", "-a", "3"]);
+        assert_eq!(output.status.code(), Some(1), "{}", input);
+        assert!(output.stdout.is_empty());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(
+            stderr.starts_with("error: ") && stderr.contains(error),
+            "{}",
+            stderr
+        );
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_equation-solver"))
+        .arg("does/not/exist")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    for args in [&["--bogus"][..], &["-p", "x"], &["-f", "xml"]] {
+        assert_eq!(solver(SYSTEM, args).status.code(), Some(2), "{:?}", args);
+    }
+}
+
+#[test]
+fn json_units() {
+    let input = "d = v * t\nv = 3 [m/s]\nt = 2 [s]";
+    for args in [&["-f", "json"][..], &["-f", "json", "--refine"]] {
+        let json: serde_json::Value = serde_json::from_str(&stdout(&solver(input, args))).unwrap();
+        let units: Vec<&str> = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["unit"].as_str().unwrap())
+            .collect();
+        assert_eq!(units, ["m", "m/s", "s"], "{:?}", args);
+    }
+}
+
+#[test]
+fn verified_digits_are_printed() {
+    let output = solver("x^3 = 0.2", &["--refine", "--verify", "--real-only"]);
+    let out = stdout(&output);
+    let mut lines = out.lines();
+    let value = lines.next().unwrap().strip_prefix("x = 0.").unwrap();
+    let digits: usize = lines
+        .find_map(|line| line.strip_prefix("x: error about "))
+        .and_then(|line| line.split(", ").nth(1)?.strip_suffix(" significant digits"))
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(digits >= 30);
+    assert_eq!(value.len(), digits);
+    assert!(value.starts_with("5848035476425732131"));
+}
@@ -0,0 +1,120 @@
+//! Parameter sweeps and homotopy continuation
+
+use nalgebra::{DMatrix, DVector};
+use num_complex::Complex64;
+use wasm_lib::{
+    equation::continuation::{track, Homotopy},
+    parse_system,
+};
+
+/// `H(x, t) = a(t) x^n - b(t)` in one variable
+struct Power<A, B> {
+    n: i32,
+    a: A,
+    b: B,
+}
+
+impl<A: Fn(f64) -> (f64, f64), B: Fn(f64) -> (f64, f64)> Homotopy for Power<A, B> {
+    fn eval(&self, x: &[Complex64], t: f64) -> DVector<Complex64> {
+        DVector::from_element(1, x[0].powi(self.n) * (self.a)(t).0 - (self.b)(t).0)
+    }
+    fn jacobian(&self, x: &[Complex64], t: f64) -> DMatrix<Complex64> {
+        DMatrix::from_element(
+            1,
+            1,
+            x[0].powi(self.n - 1) * (self.n as f64 * (self.a)(t).0),
+        )
+    }
+    fn dt(&self, x: &[Complex64], t: f64) -> DVector<Complex64> {
+        DVector::from_element(1, x[0].powi(self.n) * (self.a)(t).1 - (self.b)(t).1)
+    }
+}
+
+/// `(value, derivative)` of `c t + d`
+fn line(c: f64, d: f64) -> impl Fn(f64) -> (f64, f64) {
+    move |t| (c * t + d, c)
+}
+
+#[test]
+fn sweeps_stay_on_their_branch_where_paths_are_lost() {
+    // `x` is close to `p` or `p - 4`, and the path of `y` ends at a fold at `p = 2`
+    let system = parse_system(&[("(x - p) * (x - p + 4)", "0"), ("y^3 - 3y", "p")]).unwrap();
+    let p = system.var_id("p").unwrap();
+    let values = [0.0, 1.9, 3.5].map(Complex64::from);
+    let initial = vec![0.0.into(), 0.0.into(), (-0.5).into()];
+    let solutions = system.sweep(p, &values, initial, 100);
+    for (solution, p) in solutions.iter().zip(values) {
+        let x = solution.as_ref().unwrap()[0];
+        assert!((x - p).norm() < 1e-6, "x = {} at p = {}", x, p);
+    }
+}
+
+#[test]
+fn paths_to_large_roots_are_kept() {
+    for end in [2e4, 1e9, 1e14] {
+        let h = Power {
+            n: 1,
+            a: line(0.0, 1.0),
+            b: line(end - 1.0, 1.0),
+        };
+        let x = track(&h, vec![1.0.into()]).unwrap()[0];
+        assert!((x - end).norm() < 1e-8 * end, "{} instead of {}", x, end);
+    }
+}
+
+#[test]
+fn paths_to_infinity_are_lost() {
+    // `x = (1 - t)^(-1/n)`, however small the solution is at the start
+    for n in [1, 2, 5] {
+        for scale in [1e-6, 1.0, 1e6] {
+            let h = Power {
+                n,
+                a: line(-1.0, 1.0),
+                b: line(0.0, scale),
+            };
+            let start = Complex64::from(scale).powf(1.0 / n as f64);
+            assert!(track(&h, vec![start]).is_none(), "n = {}, {}", n, scale);
+        }
+    }
+}
+
+#[test]
+fn large_roots_are_found() {
+    let system = parse_system(&[("x^2", "400000000")]).unwrap();
+    let solutions = system.solve_polynomial(100).unwrap();
+    assert_eq!(solutions.lost, 0);
+    let mut roots: Vec<f64> = solutions.roots.iter().map(|r| r.solution[0].re).collect();
+    roots.sort_by(f64::total_cmp);
+    assert_eq!(roots.len(), 2);
+    assert!((roots[0] + 2e4).abs() < 1e-6 && (roots[1] - 2e4).abs() < 1e-6);
+
+    // one of the paths goes to the root at infinity
+    let system = parse_system(&[("x * y", "50000"), ("x", "40000")]).unwrap();
+    let solutions = system.solve_polynomial(100).unwrap();
+    assert_eq!((solutions.roots.len(), solutions.lost), (1, 1));
+
+    let system = parse_system(&[("x^3", "8000000000000")]).unwrap();
+    let x = system
+        .solve_homotopy(100, std::iter::once(1.0.into()))
+        .unwrap()[0];
+    assert!((x.powu(3) / 8e12 - 1.0).norm() < 1e-12, "{}", x);
+
+    let system = parse_system(&[("x", "30000 * p")]).unwrap();
+    let p = system.var_id("p").unwrap();
+    let values = [1.0, 2.0, 3.0].map(Complex64::from);
+    let solutions = system.sweep(p, &values, vec![0.0.into(), 1.0.into()], 100);
+    for (solution, p) in solutions.iter().zip(values) {
+        assert!((solution.as_ref().unwrap()[0] - p * 30000.0).norm() < 1e-6);
+    }
+}
+
+#[test]
+fn huge_degrees_are_not_polynomial() {
+    let system = parse_system(&[("x^4000000000 * x^4000000000", "1")]).unwrap();
+    assert_eq!(system.degrees(), None);
+    assert!(system.solve_polynomial(100).is_none());
+    let system = parse_system(&[("x^65536 * x^65536", "1")]).unwrap();
+    assert_eq!(system.degrees(), None);
+    let system = parse_system(&[("(x^2 + 1)^3", "1")]).unwrap();
+    assert_eq!(system.degrees(), Some(vec![6]));
+}
@@ -0,0 +1,153 @@
+//! Garbage input gives errors instead of panics
+
+use std::collections::HashMap;
+
+use num_complex::Complex64;
+use wasm_lib::{parse_system, Error, Side, SolverOptions};
+
+fn parse_error(left: &str, right: &str) -> Error {
+    parse_system(&[(left, right)]).unwrap_err()
+}
+
+#[test]
+fn malformed_expressions() {
+    for expr in [
+        "", "(", ")", "x +", "* x", "x ** y", "sin", "sin(", "2..3", "1e", "x $ y", "((x)", "|x",
+        "x|", ",", "sin(x,)", "3 = 4",
+    ] {
+        assert!(
+            matches!(
+                parse_error(expr, "0"),
+                Error::Parse {
+                    side: Side::Left,
+                    ..
+                }
+            ),
+            "`{}` parsed",
+            expr
+        );
+    }
+}
+
+#[test]
+fn error_position() {
+    let err = parse_system(&[("x", "1"), ("y", "2 +")]).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Parse {
+            eq: 1,
+            side: Side::Right,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn deep_nesting() {
+    let deep = "(".repeat(100_000) + "x" + &")".repeat(100_000);
+    assert!(matches!(parse_error(&deep, "0"), Error::Parse { .. }));
+    let minus = "-".repeat(100_000) + "x";
+    assert!(matches!(parse_error(&minus, "0"), Error::Parse { .. }));
+    let powers = "2^".repeat(100_000) + "x";
+    assert!(matches!(parse_error(&powers, "0"), Error::Parse { .. }));
+}
+
+#[test]
+fn long_expressions() {
+    // sums and products of any length are kept shallow, and print as they were written
+    let names: Vec<String> = (0..500).map(|i| format!("x{}", i)).collect();
+    let sum = names.join(" + ");
+    let system = parse_system(&[(sum.as_str(), "0")]).unwrap();
+    assert_eq!(system.eqs[0].left.display(&system.symbols).to_string(), sum);
+    let args: Vec<Complex64> = (0..500).map(|i| Complex64::from(i as f64)).collect();
+    assert_eq!(system.run(&args)[0], Complex64::from(124_750.0));
+
+    let sum = vec!["x"; 100_000].join(" + ");
+    let system = parse_system(&[(sum.as_str(), "1")]).unwrap();
+    assert!(system.eqs[0].left.depth() < 20);
+    let x = system.solve_with(&SolverOptions::default()).unwrap().values[0];
+    assert!((x - 1e-5).norm() < 1e-15);
+    let product = vec!["x"; 100_000].join(" * ");
+    let system = parse_system(&[(product.as_str(), "1")]).unwrap();
+    let v = system.run(&[Complex64::from(1.000_001)])[0] + 1.0;
+    assert!((v - 0.1f64.exp()).norm() < 1e-6);
+
+    // other chains still nest one level per operator
+    let difference = vec!["x"; 100_000].join(" - ");
+    assert!(matches!(parse_error(&difference, "0"), Error::Parse { .. }));
+}
+
+#[test]
+fn wrong_initial_values() {
+    let system = parse_system(&[("x^2 + y", "3"), ("sin(x)", "y")]).unwrap();
+    let solve = |name: &str, v: f64| {
+        let options = SolverOptions {
+            initial: HashMap::from([(name.to_string(), v.into())]),
+            ..SolverOptions::default()
+        };
+        system.solve_with(&options)
+    };
+    assert_eq!(solve("z", 1.0), Err(Error::UnknownVariable("z".into())));
+    assert!(matches!(solve("x", f64::NAN), Err(Error::InvalidInput(_))));
+    assert!(matches!(
+        solve("y", f64::INFINITY),
+        Err(Error::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn degenerate_options() {
+    let system = parse_system(&[("x^2 + x", "3")]).unwrap();
+    let options = SolverOptions {
+        attempts: 0,
+        ..SolverOptions::default()
+    };
+    assert_eq!(system.solve_with(&options), Err(Error::NoSolution));
+    let options = SolverOptions {
+        iterations: 0,
+        tolerance: f64::NAN,
+        ..SolverOptions::default()
+    };
+    assert!(system.solve_with(&options).is_err());
+}
+
+#[test]
+fn contradictions() {
+    let system = parse_system(&[("x + y", "1"), ("x + y", "2")]).unwrap();
+    assert_eq!(
+        system.solve_with(&SolverOptions::default()),
+        Err(Error::Inconsistent)
+    );
+    let system = parse_system(&[("x / 0", "1")]).unwrap();
+    assert!(system.solve_with(&SolverOptions::default()).is_err());
+}
+
+#[test]
+fn mismatched_units() {
+    let err = parse_system(&[("x", "2 [m]"), ("y", "x + 3 [s]")]).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Units {
+            eq: 1,
+            side: Side::Right,
+            ..
+        }
+    ));
+    let eqs = [("F", "m * 9.81 [m/s^2]"), ("m", "2 [kg]"), ("F", "3 [J]")];
+    assert!(matches!(
+        parse_system(&eqs).unwrap_err(),
+        Error::Units { eq: 2, .. }
+    ));
+    assert!(matches!(
+        parse_error("sin(2 [m])", "x"),
+        Error::Units { .. }
+    ));
+    assert!(matches!(parse_error("x^(2 [s])", "1"), Error::Units { .. }));
+    for unit in ["[foo]", "[m", "[]", "[2 m]", "[m^x]", "2 [m]]"] {
+        assert!(
+            matches!(parse_error(unit, "x"), Error::Parse { .. }),
+            "{}",
+            unit
+        );
+    }
+}
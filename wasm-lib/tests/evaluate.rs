@@ -0,0 +1,74 @@
+//! Single expressions computed without building a system
+
+use std::collections::HashMap;
+
+use num_complex::Complex64;
+use wasm_lib::{evaluate, Branch, Error};
+
+fn eval(expr: &str, bindings: &[(&str, Complex64)]) -> Result<Complex64, Error> {
+    let bindings: HashMap<String, Complex64> = bindings
+        .iter()
+        .map(|(name, v)| (name.to_string(), *v))
+        .collect();
+    evaluate(expr, &bindings, Branch::default())
+}
+
+#[test]
+fn constants_and_functions() {
+    let v = eval("sin(pi/4)^2", &[]).unwrap();
+    assert!((v - 0.5).norm() < 1e-15);
+    assert_eq!(eval("2 + 3i", &[]).unwrap(), Complex64::new(2.0, 3.0));
+    let v = eval("sqrt(-4)", &[]).unwrap();
+    assert!((v - Complex64::new(0.0, 2.0)).norm() < 1e-15);
+}
+
+#[test]
+fn bound_variables() {
+    let v = eval(
+        "x^2 + y",
+        &[("x", 3.0.into()), ("y", Complex64::i()), ("z", 1.0.into())],
+    );
+    assert!((v.unwrap() - Complex64::new(9.0, 1.0)).norm() < 1e-14);
+}
+
+#[test]
+fn branches() {
+    let bindings = HashMap::new();
+    let principal = evaluate("cbrt(-8)", &bindings, Branch::default()).unwrap();
+    assert!(principal.im > 0.0);
+    let real = Branch {
+        real_roots: true,
+        k: 0,
+    };
+    let v = evaluate("cbrt(-8)", &bindings, real).unwrap();
+    assert!((v + 2.0).norm() < 1e-15);
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(eval("2 +", &[]), Err(Error::Parse { eq: 0, .. })));
+    assert!(matches!(eval("x + 1", &[]), Err(Error::InvalidInput(_))));
+}
+
+#[test]
+fn units_are_checked() {
+    let v = eval(
+        "2 [m] * 3 [s] + x * y",
+        &[("x", 1.0.into()), ("y", 4.0.into())],
+    );
+    assert!((v.unwrap() - 10.0).norm() < 1e-12);
+    for expr in [
+        "2 [m] + 3 [s]",
+        "sin(2 [m])",
+        "x * 1 [m] + x * 1 [s]",
+        "2^(1 [s])",
+    ] {
+        let v = eval(expr, &[("x", 1.0.into())]);
+        assert!(
+            matches!(v, Err(Error::Units { eq: 0, .. })),
+            "{}: {:?}",
+            expr,
+            v
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! Isolating a variable rearranges an equation into a formula for it
+
+use num_complex::Complex64;
+use wasm_lib::{parse_system, Branch, Error, System};
+
+/// Formula for `var` from the only equation, checking that it satisfies the equation
+fn isolated(system: &System, var: &str) -> String {
+    let formula = system.isolate(0, var).unwrap();
+    let mut args = vec![Complex64::new(0.6, 0.2), Complex64::new(1.3, -0.4)];
+    args.truncate(system.var_amount);
+    let id = system.var_id(var).unwrap();
+    args[id] = formula.run(&args, system.branch);
+    let residual = system.eqs[0].calc(&args, system.branch);
+    assert!(residual.norm() < 1e-12, "{} gives {}", var, residual);
+    formula.display(&system.symbols).to_string()
+}
+
+#[test]
+fn formulas() {
+    let system = parse_system(&[("2x + 3", "7")]).unwrap();
+    assert_eq!(isolated(&system, "x"), "2");
+    let system = parse_system(&[("x + x", "y")]).unwrap();
+    assert_eq!(isolated(&system, "x"), "0.5 * y");
+    let system = parse_system(&[("y", "sqrt(x) / 2")]).unwrap();
+    assert_eq!(isolated(&system, "x"), "(2 * y)^2");
+    let system = parse_system(&[("ln(x)", "y")]).unwrap();
+    assert_eq!(isolated(&system, "x"), "e^y");
+    let system = parse_system(&[("y", "2^x")]).unwrap();
+    assert_eq!(isolated(&system, "x"), "1.4426950408889634 * ln(y)");
+    let system = parse_system(&[("1 / x", "y - 1")]).unwrap();
+    isolated(&system, "x");
+    isolated(&system, "y");
+}
+
+#[test]
+fn formulas_follow_the_branch() {
+    let mut system = parse_system(&[("x^2", "y")]).unwrap();
+    isolated(&system, "x");
+    system.branch = Branch {
+        real_roots: false,
+        k: 1,
+    };
+    isolated(&system, "x");
+}
+
+#[test]
+fn what_cannot_be_isolated() {
+    let not_isolatable = |eqs: &[(&str, &str)], var: &str| {
+        let system = parse_system(eqs).unwrap();
+        matches!(
+            system.isolate(0, var),
+            Err(Error::NotIsolatable { eq: 0, .. })
+        )
+    };
+    assert!(not_isolatable(&[("x^2 + x", "1")], "x"));
+    assert!(not_isolatable(&[("x % 3", "1")], "x"));
+    assert!(not_isolatable(&[("|x|", "1")], "x"));
+    assert!(not_isolatable(&[("arg(x)", "1")], "x"));
+    assert!(not_isolatable(&[("x", "1"), ("y", "2")], "y"));
+
+    let system = parse_system(&[("x + y", "1"), ("x", "y")]).unwrap();
+    assert_eq!(
+        system.isolate(2, "x"),
+        Err(Error::InvalidInput(
+            "There is no equation 2, only 0 to 1".into()
+        ))
+    );
+    assert_eq!(
+        system.isolate(0, "z"),
+        Err(Error::UnknownVariable("z".into()))
+    );
+}
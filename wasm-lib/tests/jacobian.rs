@@ -0,0 +1,25 @@
+//! Finite-difference Jacobians match the derivatives, so Newton steps have full length
+
+use num_complex::Complex64;
+use wasm_lib::parse_system;
+
+#[test]
+fn jacobian_matches_derivatives() {
+    let system = parse_system(&[("x^2 + 3y", "1"), ("x * y", "e^y")]).unwrap();
+    let (x, y) = (Complex64::new(2.0, 0.5), Complex64::new(1.0, -1.0));
+    let j = system.jacobian(&[x, y]);
+    let exact = [[x * 2.0, 3.0.into()], [y, x - y.exp()]];
+    for (i, row) in exact.iter().enumerate() {
+        for (k, d) in row.iter().enumerate() {
+            assert!((j[(i, k)] - d).norm() < 1e-4, "{} != {}", j[(i, k)], d);
+        }
+    }
+}
+
+#[test]
+fn newton_converges_quadratically() {
+    let system = parse_system(&[("x^2", "2")]).unwrap();
+    let mut args = [Complex64::from(3.0)];
+    assert!(system.solve_block(&mut args, &system.whole_block(), 6));
+    assert!((args[0] - 2f64.sqrt()).norm() < 1e-8);
+}
@@ -0,0 +1,151 @@
+//! Linear systems are solved directly, including singular and non-square ones
+
+use num_complex::Complex64;
+use wasm_lib::{equation::linear::LinearSolution, parse_system, Error, SolverOptions};
+
+fn solved(eqs: &[(&str, &str)]) -> LinearSolution {
+    parse_system(eqs).unwrap().solve_linear().unwrap()
+}
+
+fn close(a: &[Complex64], b: &[Complex64]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| (a - b).norm() < 1e-10)
+}
+
+fn unique(eqs: &[(&str, &str)]) -> Vec<Complex64> {
+    match solved(eqs) {
+        LinearSolution::Unique(v) => v,
+        s => panic!("{:?} gives {:?}", eqs, s),
+    }
+}
+
+#[test]
+fn unique_solutions() {
+    let v = unique(&[("2x + y", "5"), ("x - y", "1")]);
+    assert!(close(&v, &[2.0.into(), 1.0.into()]));
+    let v = unique(&[("x/2 + 3", "4")]);
+    assert!(close(&v, &[2.0.into()]));
+    let v = unique(&[("i*x", "1")]);
+    assert!(close(&v, &[Complex64::new(0.0, -1.0)]));
+    // overdetermined, but consistent
+    let v = unique(&[("x", "1"), ("2x", "2"), ("x + y", "3")]);
+    assert!(close(&v, &[1.0.into(), 2.0.into()]));
+}
+
+#[test]
+fn no_solutions() {
+    assert!(matches!(
+        solved(&[("x + y", "1"), ("x + y", "2")]),
+        LinearSolution::None
+    ));
+    assert!(matches!(
+        solved(&[("x", "1"), ("x", "2")]),
+        LinearSolution::None
+    ));
+
+    let system = parse_system(&[("x + y", "1"), ("2x + 2y", "3")]).unwrap();
+    let options = SolverOptions::default();
+    assert_eq!(
+        system.solve_with(&options).unwrap_err(),
+        Error::Inconsistent
+    );
+}
+
+#[test]
+fn infinitely_many_solutions() {
+    for (eqs, free) in [
+        (vec![("x + y", "2")], 1),
+        (vec![("x + y", "1"), ("2x + 2y", "2")], 1),
+        (vec![("x + y + z", "1"), ("x - z", "0")], 1),
+        (vec![("x + y + z", "1")], 2),
+    ] {
+        let system = parse_system(&eqs).unwrap();
+        let LinearSolution::Infinite {
+            particular,
+            null_space,
+        } = system.solve_linear().unwrap()
+        else {
+            panic!("{:?} has infinitely many solutions", eqs);
+        };
+        assert_eq!(null_space.len(), free, "{:?}", eqs);
+
+        for (c, direction) in [0.0, 1.5, -3.0].iter().zip(null_space.iter().cycle()) {
+            let v: Vec<Complex64> = particular
+                .iter()
+                .zip(direction)
+                .map(|(p, n)| p + n * c)
+                .collect();
+            assert!(system.run(&v).norm() < 1e-10, "{:?}", eqs);
+        }
+    }
+}
+
+#[test]
+fn rank_is_relative_to_the_scale() {
+    for scale in ["10^-12", "1", "10^12"] {
+        let scaled = |eqs: &[(&str, &str)]| -> Vec<(String, String)> {
+            eqs.iter()
+                .map(|(l, r)| {
+                    (
+                        format!("{} * ({})", scale, l),
+                        format!("{} * ({})", scale, r),
+                    )
+                })
+                .collect()
+        };
+        let solved = |eqs: &[(&str, &str)]| {
+            let eqs = scaled(eqs);
+            let eqs: Vec<(&str, &str)> = eqs.iter().map(|(l, r)| (&l[..], &r[..])).collect();
+            solved(&eqs)
+        };
+        // overdetermined, so not solved by elimination
+        match solved(&[("x", "1"), ("2x", "2"), ("x + y", "3")]) {
+            LinearSolution::Unique(v) => assert!(close(&v, &[1.0.into(), 2.0.into()])),
+            s => panic!("scaled by {} gives {:?}", scale, s),
+        }
+        match solved(&[("x + y", "2"), ("2x + 2y", "4")]) {
+            LinearSolution::Infinite { null_space, .. } => assert_eq!(null_space.len(), 1),
+            s => panic!("scaled by {} gives {:?}", scale, s),
+        }
+        assert!(
+            matches!(
+                solved(&[("x + y", "1"), ("x + y", "2")]),
+                LinearSolution::None
+            ),
+            "scaled by {}",
+            scale
+        );
+    }
+}
+
+#[test]
+fn solving_reports_the_free_directions() {
+    let system = parse_system(&[("x + y", "2"), ("2x + 2y", "4")]).unwrap();
+    let solution = system.solve_with(&SolverOptions::default()).unwrap();
+    // the solution of least norm, not one found from a random start
+    assert!(close(&solution.values, &[1.0.into(), 1.0.into()]));
+    assert_eq!(solution.null_space.len(), 1);
+    assert_eq!(solution.jacobians, 0);
+    let direction = &solution.null_space[0];
+    assert!((direction[0] + direction[1]).norm() < 1e-10);
+    assert!(direction[0].norm() > 0.5);
+
+    let system = parse_system(&[("x", "1"), ("y", "2")]).unwrap();
+    let solution = system.solve_with(&SolverOptions::default()).unwrap();
+    assert!(solution.null_space.is_empty());
+}
+
+#[test]
+fn nonlinear_systems_are_left_to_newton() {
+    assert!(parse_system(&[("x^2", "1")])
+        .unwrap()
+        .solve_linear()
+        .is_none());
+    assert!(parse_system(&[("x * y", "1"), ("x", "y")])
+        .unwrap()
+        .solve_linear()
+        .is_none());
+    assert!(parse_system(&[("x / 0", "1")])
+        .unwrap()
+        .solve_linear()
+        .is_none());
+}
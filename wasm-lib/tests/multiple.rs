@@ -0,0 +1,56 @@
+//! Multiple roots are found to full precision and reported with their multiplicity
+
+use wasm_lib::{parse_system, Solution, SolverOptions};
+
+fn solved(eqs: &[(&str, &str)], tolerance: f64) -> Solution {
+    let options = SolverOptions {
+        tolerance,
+        ..SolverOptions::default()
+    };
+    parse_system(eqs).unwrap().solve_with(&options).unwrap()
+}
+
+#[test]
+fn multiple_roots_converge() {
+    for (eqs, multiplicity) in [
+        (vec![("(x - 1)^2", "0")], 2),
+        (vec![("(x - 1)^3", "0")], 3),
+        (vec![("(x - 1)^2 * (x + 2)", "0")], 2),
+    ] {
+        let solution = solved(&eqs, 1e-20);
+        assert!((solution.values[0] - 1.0).norm() < 1e-9, "{:?}", eqs);
+        assert_eq!(solution.multiplicity, multiplicity, "{:?}", eqs);
+    }
+
+    let solution = solved(&[("(x - y)^2", "0"), ("x + y", "2")], 1e-20);
+    assert!((solution.values[0] - 1.0).norm() < 1e-9);
+    assert_eq!(solution.multiplicity, 2);
+}
+
+#[test]
+fn simple_roots_have_multiplicity_one() {
+    assert_eq!(solved(&[("x^2", "2")], 1e-8).multiplicity, 1);
+    assert_eq!(
+        solved(&[("x + y", "3"), ("x * y", "1")], 1e-8).multiplicity,
+        1
+    );
+    assert_eq!(solved(&[("2x", "3")], 1e-8).multiplicity, 1);
+}
+
+#[test]
+fn close_simple_roots_are_not_multiple() {
+    // the roots are 2e-6 apart, well within a probe of fixed size
+    for tolerance in [1e-8, 1e-20] {
+        let options = SolverOptions {
+            tolerance,
+            verify: true,
+            ..SolverOptions::default()
+        };
+        let solution = parse_system(&[("(x - 1)^2", "0.000000000001")])
+            .unwrap()
+            .solve_with(&options)
+            .unwrap();
+        assert_eq!(solution.multiplicity, 1, "{}", tolerance);
+        assert!(!solution.verification.unwrap().multiple, "{}", tolerance);
+    }
+}
@@ -0,0 +1,99 @@
+//! Known values of some variables, given at solve time instead of in the equations
+
+use std::collections::HashMap;
+
+use num_complex::Complex64;
+use wasm_lib::{parse_system, parsing::symbols::SymbolKind, Error, SolverOptions};
+
+fn params(values: &[(&str, f64)]) -> HashMap<String, Complex64> {
+    values
+        .iter()
+        .map(|(name, v)| (name.to_string(), Complex64::from(v)))
+        .collect()
+}
+
+#[test]
+fn params_are_not_unknowns() {
+    let system = parse_system(&[("F", "m * a"), ("v", "a * t")]).unwrap();
+    let bound = system.with_params(&params(&[("m", 2.0), ("t", 3.0), ("unused", 1.0)]));
+    assert_eq!(system.var_amount, 5);
+    assert_eq!(bound.var_amount, 3);
+    assert_eq!(bound.symbols.names(), ["F", "a", "v"]);
+
+    let bound = system.bind(&[None, Some(2.0.into()), None, None, Some(3.0.into())]);
+    assert_eq!(bound.symbols.names(), ["F", "a", "v"]);
+}
+
+#[test]
+fn one_system_solved_with_different_params() {
+    let system = parse_system(&[("m * g", "k * x")]).unwrap();
+    for (m, k) in [(1.0, 10.0), (2.0, 10.0), (2.0, 4.0)] {
+        let options = SolverOptions {
+            params: params(&[("m", m), ("g", 9.81), ("k", k)]),
+            ..SolverOptions::default()
+        };
+        let solution = system.solve_with(&options).unwrap();
+        assert_eq!(solution.names, ["x"]);
+        let x = solution.get("x").unwrap();
+        assert!((x - m * 9.81 / k).norm() < 1e-9, "{}", x);
+    }
+}
+
+#[test]
+fn every_variable_known() {
+    let system = parse_system(&[("x + y", "3")]).unwrap();
+    let holds = SolverOptions {
+        params: params(&[("x", 1.0), ("y", 2.0)]),
+        ..SolverOptions::default()
+    };
+    assert!(system.solve_with(&holds).unwrap().values.is_empty());
+    let fails = SolverOptions {
+        params: params(&[("x", 1.0), ("y", 1.0)]),
+        ..SolverOptions::default()
+    };
+    assert!(system.solve_with(&fails).is_err());
+}
+
+#[test]
+fn parameters_need_a_value() {
+    let mut system = parse_system(&[("m * g", "k * x")]).unwrap();
+    for name in ["m", "g"] {
+        let id = system.symbols.id(name).unwrap();
+        system.symbols.get_mut(id).unwrap().kind = SymbolKind::Parameter;
+    }
+    let options = SolverOptions {
+        params: params(&[("m", 2.0), ("k", 4.0)]),
+        ..SolverOptions::default()
+    };
+    let err = system.solve_with(&options).unwrap_err();
+    assert_eq!(
+        err,
+        Error::InvalidInput("No value given for the parameter `g`".into())
+    );
+}
+
+#[test]
+fn solutions_within_bounds() {
+    let mut system = parse_system(&[("x^2", "4")]).unwrap();
+    for (bounds, root) in [
+        ((0.0, f64::INFINITY), Some(2.0)),
+        ((-3.0, -1.0), Some(-2.0)),
+        ((5.0, 10.0), None),
+    ] {
+        system.symbols.get_mut(0).unwrap().bounds = Some(bounds);
+        let options = SolverOptions {
+            attempts: 10,
+            ..SolverOptions::default()
+        };
+        match (system.solve_with(&options), root) {
+            (Ok(solution), Some(root)) => assert!((solution.values[0] - root).norm() < 1e-6),
+            (Err(Error::NoSolution), None) => (),
+            (result, _) => panic!("{:?} within {:?}", result, bounds),
+        }
+    }
+
+    let mut linear = parse_system(&[("2 x", "3")]).unwrap();
+    linear.symbols.get_mut(0).unwrap().bounds = Some((0.0, 1.0));
+    let result = linear.solve_with(&SolverOptions::default());
+    assert_eq!(result.unwrap_err(), Error::NoSolution);
+}
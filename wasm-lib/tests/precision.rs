@@ -0,0 +1,82 @@
+//! Refined solutions keep the digits that `f64` cannot hold
+
+use std::collections::HashMap;
+
+use wasm_lib::equation::double_double::{ComplexDD, DoubleDouble};
+use wasm_lib::{parse_system, SolverOptions};
+
+/// The refined solution found from `x = 1`
+fn refined(eqs: &[(&str, &str)]) -> Vec<String> {
+    let options = SolverOptions {
+        refine: true,
+        initial: HashMap::from([("x".to_string(), 1.0.into())]),
+        ..SolverOptions::default()
+    };
+    let solution = parse_system(eqs).unwrap().solve_with(&options).unwrap();
+    solution
+        .precise
+        .unwrap()
+        .iter()
+        .map(|v| v.to_string())
+        .collect()
+}
+
+#[test]
+fn refine_to_32_digits() {
+    assert_eq!(
+        refined(&[("x^2", "2")]),
+        ["1.4142135623730950488016887242097"]
+    );
+    assert_eq!(
+        refined(&[("e^x", "10")]),
+        ["2.3025850929940456840179914546844"]
+    );
+    assert_eq!(
+        refined(&[("x", "1/3")]),
+        ["0.33333333333333333333333333333333"]
+    );
+    assert_eq!(
+        refined(&[("x + y", "3"), ("x * y", "1")]),
+        [
+            "0.38196601125010515179541316563436",
+            "2.6180339887498948482045868343656"
+        ]
+    );
+}
+
+#[test]
+fn literals_keep_every_digit() {
+    assert_eq!(refined(&[("x", "0.1")]), ["0.1"]);
+    // the 32nd digit is a rounding of a 5
+    assert!(refined(&[("x^2", "0.2")])[0].starts_with("0.4472135954999579392818347337462"));
+    assert_eq!(
+        refined(&[("x", "sqrt(2) * 0.001")]),
+        ["0.0014142135623730950488016887242097"]
+    );
+}
+
+#[test]
+fn large_angles_are_reduced() {
+    let (sin, cos) = DoubleDouble::from(1e19).sin_cos();
+    let expected: DoubleDouble = "0.92706316604865038523412228966494".parse().unwrap();
+    assert!((sin + expected).abs().hi < 1e-28, "{}", sin);
+    let expected: DoubleDouble = "0.37490516955071783015322054600961".parse().unwrap();
+    assert!((cos + expected).abs().hi < 1e-28, "{}", cos);
+    // past the reduced range only the `f64` digits are kept
+    let (sin, _) = DoubleDouble::from(1e40).sin_cos();
+    assert_eq!(sin.hi, 1e40f64.sin());
+    assert!(refined(&[("x", "sin(10000000000000000000)")])[0]
+        .starts_with("-0.927063166048650385234122289"));
+}
+
+#[test]
+fn overflow_is_infinite() {
+    for x in [709.9, 710.0, 1000.0] {
+        let exp = ComplexDD::new(x.into(), DoubleDouble::ZERO).exp();
+        assert_eq!(exp.re.hi, f64::INFINITY);
+        assert_eq!(exp.im.hi, 0.0);
+    }
+    let big = DoubleDouble::from(1e300);
+    assert_eq!((big * big).hi, f64::INFINITY);
+    assert_eq!((big * big + 1.0.into()).hi, f64::INFINITY);
+}
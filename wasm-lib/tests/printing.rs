@@ -0,0 +1,116 @@
+//! Expressions print as source and as LaTeX with only the parentheses they need
+
+use num_complex::Complex64;
+use wasm_lib::parse_system;
+
+/// `left = right` printed as source and as LaTeX, checking that the source parses back to an
+/// equation of the same value
+fn printed(left: &str, right: &str) -> (String, String) {
+    let system = parse_system(&[(left, right)]).unwrap();
+    let (text, latex) = system.preview().remove(0);
+    let (left_text, right_text) = text.split_once(" = ").unwrap();
+    let reparsed = parse_system(&[(left_text, right_text)]).unwrap();
+    let args =
+        [(0.7, -0.3), (-1.2, 0.4), (2.1, 0.9), (0.3, 1.1)].map(|(re, im)| Complex64::new(re, im));
+    let (before, after) = (
+        system.run(&args[..system.var_amount]),
+        reparsed.run(&args[..system.var_amount]),
+    );
+    assert!(
+        (&before - &after).norm() < 1e-12 * before.norm().max(1.0),
+        "`{} = {}` printed as `{}`",
+        left,
+        right,
+        text
+    );
+    (text, latex)
+}
+
+fn text(expr: &str) -> String {
+    printed(expr, "0").0.trim_end_matches(" = 0").to_string()
+}
+
+fn latex(expr: &str) -> String {
+    printed(expr, "0").1.trim_end_matches(" = 0").to_string()
+}
+
+#[test]
+fn negation_and_powers() {
+    assert_eq!(text("-x^2"), "-x^2");
+    assert_eq!(text("-(x^2)"), "-x^2");
+    assert_eq!(text("(-x)^2"), "(-x)^2");
+    assert_eq!(text("(-2)^2"), "(-2)^2");
+    assert_eq!(text("2 * -x^2"), "2 * -x^2");
+    assert_eq!(text("e^(-x)"), "e^-x");
+    assert_eq!(text("a^(b^c)"), "a^b^c");
+    assert_eq!(text("(a^b)^c"), "(a^b)^c");
+
+    assert_eq!(latex("-x^2"), "-x^{2}");
+    assert_eq!(latex("(-x)^2"), "\\left(-x\\right)^{2}");
+    assert_eq!(latex("2 * (-x)^2"), "2 \\cdot \\left(-x\\right)^{2}");
+    assert_eq!(latex("x^-2"), "x^{-2}");
+    assert_eq!(latex("a^b^c"), "a^{b^{c}}");
+    assert_eq!(latex("(a^b)^c"), "\\left(a^{b}\\right)^{c}");
+}
+
+#[test]
+fn sums_and_products() {
+    assert_eq!(text("(a - b) - c"), "a - b - c");
+    assert_eq!(text("a + (b + c)"), "a + b + c");
+    assert_eq!(text("a - (b - c)"), "a - (b - c)");
+    assert_eq!(text("a - (b + c)"), "a - (b + c)");
+    assert_eq!(text("-(a + b)"), "-(a + b)");
+    assert_eq!(text("(a + b) * c"), "(a + b) * c");
+    assert_eq!(text("x % (y % z)"), "x % (y % z)");
+
+    assert_eq!(latex("a - (b - c)"), "a - \\left(b - c\\right)");
+    assert_eq!(latex("(a + b) * c"), "\\left(a + b\\right) \\cdot c");
+    assert_eq!(latex("2 x_1"), "2 \\cdot x_{1}");
+}
+
+#[test]
+fn fractions() {
+    assert_eq!(text("a / b / c"), "a / b / c");
+    assert_eq!(text("a / (b * c)"), "a / (b * c)");
+    assert_eq!(text("a * (b / c)"), "a * (b / c)");
+    assert_eq!(text("(a + b) / (c - d)"), "(a + b) / (c - d)");
+    assert_eq!(text("(a / b)^2"), "(a / b)^2");
+
+    // a fraction groups its parts, so they need no parentheses of their own
+    assert_eq!(latex("(a + b) / (c - d)"), "\\frac{a + b}{c - d}");
+    assert_eq!(latex("a / (b * c)"), "\\frac{a}{b \\cdot c}");
+    assert_eq!(latex("a / b / c"), "\\frac{\\frac{a}{b}}{c}");
+    assert_eq!(latex("a * (b / c)"), "a \\cdot \\frac{b}{c}");
+    assert_eq!(latex("(a / b)^2"), "\\left(\\frac{a}{b}\\right)^{2}");
+}
+
+#[test]
+fn functions() {
+    assert_eq!(text("sin(x)^2"), "sin(x)^2");
+    assert_eq!(text("sin((x^2))"), "sin(x^2)");
+    assert_eq!(text("|(x - 1)|"), "|x - 1|");
+
+    assert_eq!(latex("sin(x)^2"), "\\sin\\left(x\\right)^{2}");
+    assert_eq!(latex("ln(x) / 2"), "\\frac{\\ln\\left(x\\right)}{2}");
+    assert_eq!(latex("sqrt(x + 1)"), "\\sqrt{x + 1}");
+    assert_eq!(latex("cbrt(x)"), "\\sqrt[3]{x}");
+    assert_eq!(latex("|x - 1|"), "\\left|x - 1\\right|");
+}
+
+#[test]
+fn equations() {
+    assert_eq!(
+        printed("m * a", "2 [kg] * 3 [m/s^2]"),
+        (
+            "m * a = 2 [kg] * 3 [m/s^2]".to_string(),
+            "m \\cdot a = 2\\,\\mathrm{kg} \\cdot 3\\,\\mathrm{m/s^2}".to_string()
+        )
+    );
+    assert_eq!(
+        printed("x' + v_max", "theta / 2"),
+        (
+            "x' + v_max = theta / 2".to_string(),
+            "x' + v_{max} = \\frac{\\mathrm{theta}}{2}".to_string()
+        )
+    );
+}
@@ -0,0 +1,190 @@
+//! Expressions can be evaluated in number types from outside the crate
+
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+use num_complex::Complex64;
+use wasm_lib::{parse_system, Branch, Scalar};
+
+/// `v + d ε` with `ε² = 0`, so functions of it carry their derivative along
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Dual {
+    v: f64,
+    d: f64,
+}
+
+impl Add for Dual {
+    type Output = Self;
+    fn add(self, o: Self) -> Self {
+        Dual {
+            v: self.v + o.v,
+            d: self.d + o.d,
+        }
+    }
+}
+
+impl Sub for Dual {
+    type Output = Self;
+    fn sub(self, o: Self) -> Self {
+        self + -o
+    }
+}
+
+impl Mul for Dual {
+    type Output = Self;
+    fn mul(self, o: Self) -> Self {
+        Dual {
+            v: self.v * o.v,
+            d: self.d * o.v + self.v * o.d,
+        }
+    }
+}
+
+impl Div for Dual {
+    type Output = Self;
+    fn div(self, o: Self) -> Self {
+        Dual {
+            v: self.v / o.v,
+            d: (self.d * o.v - self.v * o.d) / (o.v * o.v),
+        }
+    }
+}
+
+impl Rem for Dual {
+    type Output = Self;
+    fn rem(self, o: Self) -> Self {
+        let q = (self.v / o.v).trunc();
+        Dual {
+            v: self.v % o.v,
+            d: self.d - o.d * q,
+        }
+    }
+}
+
+impl Neg for Dual {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Dual {
+            v: -self.v,
+            d: -self.d,
+        }
+    }
+}
+
+impl Scalar for Dual {
+    fn from_f64(v: f64) -> Self {
+        Dual { v, d: 0.0 }
+    }
+    fn to_complex(self) -> Complex64 {
+        self.v.into()
+    }
+    fn i() -> Self {
+        Dual::from_f64(f64::NAN)
+    }
+    fn abs(self) -> Self {
+        Dual {
+            v: self.v.abs(),
+            d: self.d * self.v.signum(),
+        }
+    }
+    fn arg(self) -> Self {
+        Dual::from_f64(Scalar::arg(self.v))
+    }
+    fn exp(self) -> Self {
+        let v = self.v.exp();
+        Dual { v, d: self.d * v }
+    }
+    fn ln(self) -> Self {
+        Dual {
+            v: self.v.ln(),
+            d: self.d / self.v,
+        }
+    }
+    fn sqrt(self) -> Self {
+        let v = self.v.sqrt();
+        Dual {
+            v,
+            d: self.d / (2.0 * v),
+        }
+    }
+    fn sin(self) -> Self {
+        Dual {
+            v: self.v.sin(),
+            d: self.d * self.v.cos(),
+        }
+    }
+    fn cos(self) -> Self {
+        Dual {
+            v: self.v.cos(),
+            d: -self.d * self.v.sin(),
+        }
+    }
+}
+
+#[test]
+fn dual_numbers_give_derivatives() {
+    let system = parse_system(&[("x^3 + tan(x) / sqrt(x) - cosh(x)", "0")]).unwrap();
+    let x = 0.7_f64;
+    let out = system.eqs[0]
+        .left
+        .run(&[Dual { v: x, d: 1.0 }], Branch::default());
+
+    let value = x.powi(3) + x.tan() / x.sqrt() - x.cosh();
+    let derivative = 3.0 * x * x + (1.0 / (x.cos().powi(2) * x.sqrt()))
+        - x.tan() / (2.0 * x.powf(1.5))
+        - x.sinh();
+    assert!((out.v - value).abs() < 1e-12, "{} != {}", out.v, value);
+    assert!(
+        (out.d - derivative).abs() < 1e-12,
+        "{} != {}",
+        out.d,
+        derivative
+    );
+}
+
+#[test]
+fn real_evaluation_matches_complex() {
+    let system =
+        parse_system(&[("x^2 + ln(x) * atan(x) - cbrt(x) % 0.3", "|x| + asin(x / 9)")]).unwrap();
+    for x in [0.5, 2.0, 7.25] {
+        let real = system.eqs[0].calc(&[x], Branch::default());
+        let complex = system.eqs[0].calc(&[Complex64::from(x)], Branch::default());
+        assert!((real - complex.re).abs() < 1e-12 && complex.im.abs() < 1e-12);
+    }
+    // values with an imaginary part are NaN
+    let system = parse_system(&[("sqrt(x)", "ln(x)")]).unwrap();
+    assert!(system.eqs[0].calc(&[-4.0], Branch::default()).is_nan());
+
+    // other branches are real where they differ by a real step, and NaN otherwise
+    let branch = Branch {
+        real_roots: false,
+        k: 1,
+    };
+    for (expr, x) in [
+        ("sqrt(x)", 4.0),
+        ("asin(x)", 0.5),
+        ("acos(x)", 0.5),
+        ("atan(x)", 2.0),
+    ] {
+        let system = parse_system(&[(expr, "0")]).unwrap();
+        let real = system.eqs[0].calc(&[x], branch);
+        let complex = system.eqs[0].calc(&[Complex64::from(x)], branch);
+        assert!(
+            (real - complex.re).abs() < 1e-12 && complex.im.abs() < 1e-12,
+            "{}",
+            expr
+        );
+    }
+    for expr in [
+        "ln(x)", "x^0.5", "cbrt(x)", "asinh(x)", "acosh(x)", "atanh(x)",
+    ] {
+        let system = parse_system(&[(expr, "0")]).unwrap();
+        assert!(system.eqs[0].calc(&[0.5], branch).is_nan(), "{}", expr);
+    }
+    let real_roots = Branch {
+        real_roots: true,
+        k: 1,
+    };
+    let system = parse_system(&[("cbrt(x)", "x^(1/3)")]).unwrap();
+    let (left, right) = system.eqs[0].sides(&[-8.0], real_roots);
+    assert_eq!((left, right), (-2.0, -2.0));
+}
@@ -0,0 +1,71 @@
+//! Simplified expressions are in a flat canonical form, which simplifying again keeps
+
+use num_complex::Complex64;
+use wasm_lib::{parse_system, Branch};
+
+/// `expr` simplified and printed, checking that simplifying keeps its value and is idempotent
+fn simplified(expr: &str) -> String {
+    let system = parse_system(&[(expr, "0")]).unwrap();
+    let expr_node = &system.eqs[0].left;
+    let simple = expr_node.simplify(system.branch);
+    assert_eq!(simple.simplify(system.branch), simple, "`{}`", expr);
+
+    let args = [(0.7, -0.3), (-1.2, 0.4), (2.1, 0.9)].map(|(re, im)| Complex64::new(re, im));
+    let before: Complex64 = expr_node.run(&args, Branch::default());
+    let after: Complex64 = simple.run(&args, Branch::default());
+    if before.is_finite() {
+        assert!(
+            (before - after).norm() < 1e-12 * before.norm().max(1.0),
+            "`{}`",
+            expr
+        );
+    }
+
+    simple.display(&system.symbols).to_string()
+}
+
+#[test]
+fn constants_and_identities() {
+    assert_eq!(simplified("2*pi"), (2.0 * std::f64::consts::PI).to_string());
+    assert_eq!(simplified("x*1 + 0"), "x");
+    assert_eq!(simplified("--x"), "x");
+    assert_eq!(simplified("x^2 - 2^-1"), "x^2 - 0.5");
+    assert_eq!(simplified("x - i"), "x - i");
+    assert_eq!(simplified("3 - 2*i + x"), "x + 3 - 2 * i");
+}
+
+#[test]
+fn like_terms_and_factors_merge() {
+    assert_eq!(simplified("x + x"), "2 * x");
+    assert_eq!(simplified("x*y - 2*y*x"), "-x * y");
+    assert_eq!(simplified("x^2*x^-2"), "1");
+    assert_eq!(simplified("x^(-1/2)*x"), "x^0.5");
+    assert_eq!(simplified("(1+i)*x + (1-i)*x"), "2 * x");
+    assert_eq!(simplified("i*x + i*x"), "2 * i * x");
+    assert_eq!(simplified("(1-2i)*x*y"), "(1 - 2 * i) * x * y");
+    // division by zero is kept so it still fails
+    assert_eq!(simplified("x/0"), "x / 0");
+}
+
+#[test]
+fn products_are_flat() {
+    assert_eq!(simplified("x^-2"), "1 / x^2");
+    assert_eq!(simplified("x*y/2"), "0.5 * x * y");
+    assert_eq!(simplified("2*x*(3*y)"), "6 * x * y");
+    assert_eq!(simplified("-x*2"), "-2 * x");
+    assert_eq!(simplified("x/y/z"), "x / (y * z)");
+}
+
+#[test]
+fn order_does_not_matter() {
+    let system = parse_system(&[("x*y + 2*x", "y^3/x"), ("2*x + y*x", "x^-1*y^3")]).unwrap();
+    let [a, b] = [&system.eqs[0], &system.eqs[1]];
+    assert_eq!(
+        a.left.simplify(system.branch),
+        b.left.simplify(system.branch)
+    );
+    assert_eq!(
+        a.right.simplify(system.branch),
+        b.right.simplify(system.branch)
+    );
+}
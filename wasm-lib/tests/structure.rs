@@ -0,0 +1,179 @@
+//! Structural analysis: splitting systems into blocks, and finding over and under-constrained
+//! parts
+
+use num_complex::Complex64;
+use wasm_lib::{
+    equation::structure::{Block, Diagnostic},
+    parse_system, System,
+};
+
+/// The blocks as `(eqs, vars)` with sorted variables, checking that every equation only uses
+/// variables of its own or earlier blocks
+fn blocks(system: &System) -> Vec<(Vec<usize>, Vec<usize>)> {
+    let blocks = system.blocks().unwrap();
+    let incidence = system.incidence();
+    let mut known = vec![];
+    for Block { eqs, vars } in &blocks {
+        known.extend(vars);
+        for &eq in eqs {
+            assert!(incidence[eq].iter().all(|v| known.contains(v)));
+        }
+    }
+    blocks
+        .into_iter()
+        .map(|mut b| {
+            b.vars.sort_unstable();
+            (b.eqs, b.vars)
+        })
+        .collect()
+}
+
+#[test]
+fn triangular_systems_split_into_single_equations() {
+    let system = parse_system(&[("x", "1"), ("x + y", "3"), ("y * z", "4")]).unwrap();
+    assert_eq!(
+        blocks(&system),
+        [(vec![0], vec![0]), (vec![1], vec![1]), (vec![2], vec![2])]
+    );
+
+    let system = parse_system(&[("y * z", "4"), ("x + y", "3"), ("x", "1")]).unwrap();
+    assert_eq!(
+        blocks(&system),
+        [(vec![2], vec![2]), (vec![1], vec![0]), (vec![0], vec![1])]
+    );
+}
+
+#[test]
+fn coupled_equations_share_a_block() {
+    let system = parse_system(&[("z", "x * y"), ("x - y", "1"), ("x + y", "3")]).unwrap();
+    assert_eq!(
+        blocks(&system),
+        [(vec![1, 2], vec![1, 2]), (vec![0], vec![0])]
+    );
+
+    let system = parse_system(&[("x^2 + y", "1"), ("x + y^2", "1"), ("z", "y")]).unwrap();
+    assert_eq!(blocks(&system)[0].0, vec![0, 1]);
+    assert_eq!(blocks(&system).len(), 2);
+}
+
+#[test]
+fn structurally_singular_systems_have_no_blocks() {
+    assert!(parse_system(&[("x + y", "1")]).unwrap().blocks().is_none());
+    let system = parse_system(&[("x", "1"), ("x^2", "1"), ("y + z", "0")]).unwrap();
+    assert!(system.blocks().is_none());
+}
+
+#[test]
+fn blocks_are_solved_in_order() {
+    let system = parse_system(&[("y * z", "4"), ("x + y^2", "5"), ("e^x", "e")]).unwrap();
+    let start = std::iter::repeat(Complex64::new(0.5, 0.1));
+    let solution = system.solve_blocks(100, start).unwrap();
+    assert!(system.run(&solution).norm() < 1e-8);
+    let x = system.var_id("x").unwrap();
+    assert!((solution[x] - 1.0).norm() < 1e-8);
+}
+
+#[test]
+fn long_chains_do_not_overflow_the_stack() {
+    const N: usize = 100_000;
+    let parsed = |eqs: Vec<(String, String)>| {
+        let eqs: Vec<(&str, &str)> = eqs.iter().map(|(l, r)| (&l[..], &r[..])).collect();
+        parse_system(&eqs).unwrap()
+    };
+
+    // `x_N = x_(N-1)`, ..., `x_1 = 1`, so each block depends on the next equation
+    let chain = parsed(
+        (1..=N)
+            .rev()
+            .map(|i| match i {
+                1 => ("x_1".to_string(), "1".to_string()),
+                i => (format!("x_{}", i), format!("x_{}", i - 1)),
+            })
+            .collect(),
+    );
+    let blocks = chain.blocks().unwrap();
+    assert_eq!(blocks.len(), N);
+    assert_eq!(blocks[0].eqs, [N - 1]);
+    assert_eq!(blocks[N - 1].eqs, [0]);
+    assert!(chain.analyze().is_empty());
+
+    // `x_0 + x_1 = 1`, ..., `x_(N-2) + x_(N-1) = 1`, then `x_0 = 1`, which takes the variable
+    // of the first equation and so rematches every one along the chain
+    let mut eqs: Vec<(String, String)> = (1..N)
+        .map(|i| (format!("x_{} + x_{}", i - 1, i), "1".to_string()))
+        .collect();
+    eqs.push(("x_0".to_string(), "1".to_string()));
+    let rematched = parsed(eqs);
+    let blocks = rematched.blocks().unwrap();
+    assert_eq!(blocks.len(), N);
+    assert_eq!(blocks[0].eqs, [N - 1]);
+    assert!(rematched.analyze().is_empty());
+}
+
+fn analyzed(eqs: &[(&str, &str)]) -> Vec<Diagnostic> {
+    let mut system = parse_system(eqs).unwrap();
+    system.simplify();
+    system.analyze()
+}
+
+#[test]
+fn square_systems_have_no_diagnostics() {
+    assert_eq!(analyzed(&[("x + y", "3"), ("x * y", "1")]), []);
+    assert_eq!(analyzed(&[("x", "1"), ("x + y", "3"), ("y * z", "4")]), []);
+}
+
+#[test]
+fn count_mismatches() {
+    assert_eq!(
+        analyzed(&[("x + y", "1")]),
+        [
+            Diagnostic::CountMismatch { eqs: 1, vars: 2 },
+            Diagnostic::Underdetermined {
+                vars: vec![0, 1],
+                surplus: 1
+            },
+        ]
+    );
+    assert_eq!(
+        analyzed(&[("x", "1"), ("x", "2"), ("y", "3")]),
+        [
+            Diagnostic::CountMismatch { eqs: 3, vars: 2 },
+            Diagnostic::Overdetermined {
+                eqs: vec![0, 1],
+                surplus: 1
+            },
+        ]
+    );
+}
+
+#[test]
+fn square_but_structurally_singular() {
+    // the first two equations both only hold `x`, leaving `y` and `z` with a single equation
+    assert_eq!(
+        analyzed(&[("x", "1"), ("x^2", "1"), ("y + z", "0")]),
+        [
+            Diagnostic::Overdetermined {
+                eqs: vec![0, 1],
+                surplus: 1
+            },
+            Diagnostic::Underdetermined {
+                vars: vec![1, 2],
+                surplus: 1
+            },
+        ]
+    );
+}
+
+#[test]
+fn variables_simplified_away_are_unused() {
+    assert_eq!(
+        analyzed(&[("x + 0*y", "1"), ("x", "2")]),
+        [
+            Diagnostic::UnusedVar(1),
+            Diagnostic::Overdetermined {
+                eqs: vec![0, 1],
+                surplus: 1
+            },
+        ]
+    );
+}
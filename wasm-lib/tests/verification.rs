@@ -0,0 +1,49 @@
+//! Verification reports tell how many digits of a solution are right
+
+use wasm_lib::{equation::verification::Verification, parse_system, Solution, SolverOptions};
+
+fn verified(eqs: &[(&str, &str)]) -> (Solution, Verification) {
+    let options = SolverOptions {
+        verify: true,
+        ..SolverOptions::default()
+    };
+    let mut solution = parse_system(eqs).unwrap().solve_with(&options).unwrap();
+    let verification = solution.verification.take().unwrap();
+    (solution, verification)
+}
+
+#[test]
+fn simple_roots_are_accurate() {
+    let (solution, report) = verified(&[("x + y", "3"), ("x * y", "1")]);
+    assert!(!report.multiple);
+    assert!(report.residual < 1e-8);
+    assert!(report.condition < 10.0);
+    for (v, error) in solution.values.iter().zip(&report.errors) {
+        assert!(Verification::digits(*v, *error) >= 10);
+    }
+}
+
+#[test]
+fn multiple_roots_lose_digits() {
+    let (solution, report) = verified(&[("(x - 1)^2", "0")]);
+    assert!(report.multiple);
+    let error = (solution.values[0] - 1.0).norm();
+    assert!(report.errors[0] > error / 2.0 && report.errors[0] < error * 2.0);
+    assert!(Verification::digits(solution.values[0], report.errors[0]) <= 6);
+}
+
+#[test]
+fn refined_roots_have_more_digits() {
+    let options = SolverOptions {
+        verify: true,
+        refine: true,
+        ..SolverOptions::default()
+    };
+    let solution = parse_system(&[("x^2", "0.2")])
+        .unwrap()
+        .solve_with(&options)
+        .unwrap();
+    let report = solution.verification.unwrap();
+    assert!(report.residual < 1e-30);
+    assert!(Verification::digits(solution.values[0], report.errors[0]) >= 30);
+}
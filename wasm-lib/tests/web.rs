@@ -3,11 +3,392 @@
 #![cfg(target_arch = "wasm32")]
 
 extern crate wasm_bindgen_test;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
 use wasm_bindgen_test::*;
+// the native `evaluate` would shadow the exported one
+use wasm_lib::wasm::evaluate;
+use wasm_lib::*;
 
 wasm_bindgen_test_configure!(run_in_browser);
 
+fn eqs(eqs: &[(&str, &str)]) -> JsValue {
+    serde_wasm_bindgen::to_value(eqs).unwrap()
+}
+
+/// A JS object, rather than the `Map` that `to_value` makes of a `HashMap`
+fn object<V: Serialize>(entries: &[(&str, V)]) -> JsValue {
+    let map: HashMap<&str, &V> = entries.iter().map(|(k, v)| (*k, v)).collect();
+    map.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .unwrap()
+}
+
+/// What `CompiledSystem::solve` returns
+#[derive(Deserialize)]
+struct Solved {
+    solution: Vec<(String, (f64, f64), String)>,
+    precise: Option<Vec<(String, String)>>,
+    enclosure: Option<Vec<[(f64, f64); 2]>>,
+    multiplicity: usize,
+    verification: Option<Verified>,
+}
+
+#[derive(Deserialize)]
+struct Verified {
+    residual: f64,
+    condition: f64,
+    digits: Vec<usize>,
+    multiple: bool,
+}
+
+/// Values that are not a list of `[left, right]` pairs
+fn not_eqs() -> Vec<JsValue> {
+    vec![
+        JsValue::UNDEFINED,
+        JsValue::NULL,
+        JsValue::from(5),
+        JsValue::from_str("x = 1"),
+        serde_wasm_bindgen::to_value(&["x", "1"]).unwrap(),
+        serde_wasm_bindgen::to_value(&[("x", 1)]).unwrap(),
+    ]
+}
+
 #[wasm_bindgen_test]
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
+
+#[wasm_bindgen_test]
+fn malformed_equations() {
+    for v in not_eqs() {
+        let err = solve(
+            v.clone(),
+            JsValue::UNDEFINED,
+            100,
+            JsValue::UNDEFINED,
+            false,
+            0,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), "invalid_input");
+        assert!(preview(v.clone()).is_err());
+        assert!(analyze(v.clone(), JsValue::UNDEFINED, false, 0).is_err());
+        assert!(solve_all(v.clone(), JsValue::UNDEFINED, 100, false, 0).is_err());
+        assert!(CompiledSystem::new(v).is_err());
+    }
+}
+
+#[wasm_bindgen_test]
+fn parse_errors() {
+    let err = preview(eqs(&[("x", "1"), ("y", "(2 +")])).unwrap_err();
+    assert_eq!(err.kind(), "parse");
+    assert_eq!(err.eq, 1);
+    assert!(err.second);
+    let deep = "(".repeat(100_000) + "x" + &")".repeat(100_000);
+    assert!(preview(eqs(&[(&deep, "0")])).is_err());
+}
+
+#[derive(Deserialize)]
+struct Preview {
+    text: String,
+    latex: String,
+}
+
+#[wasm_bindgen_test]
+fn previews() {
+    let previews = preview(eqs(&[("-x^2", "(-x)^2"), ("y", "x / 2")])).unwrap();
+    let previews: Vec<Preview> = previews
+        .into_iter()
+        .map(|v| serde_wasm_bindgen::from_value(v).unwrap())
+        .collect();
+    assert_eq!(previews[0].text, "-x^2 = (-x)^2");
+    assert_eq!(previews[0].latex, "-x^{2} = \\left(-x\\right)^{2}");
+    assert_eq!(previews[1].text, "y = x / 2");
+    assert_eq!(previews[1].latex, "y = \\frac{x}{2}");
+}
+
+#[derive(Deserialize)]
+struct Warned {
+    kind: String,
+    msg: String,
+    eqs: Vec<usize>,
+}
+
+#[wasm_bindgen_test]
+fn warnings_count_equations_from_0() {
+    let system = eqs(&[("y", "2"), ("x", "1"), ("2x", "3")]);
+    let warnings = analyze(system, JsValue::UNDEFINED, false, 0).unwrap();
+    let warning: Warned = warnings
+        .into_iter()
+        .map(|v| serde_wasm_bindgen::from_value(v).unwrap())
+        .find(|w: &Warned| w.kind == "overdetermined")
+        .unwrap();
+    assert_eq!(warning.eqs, [1, 2]);
+    assert!(warning.msg.contains("equations 1, 2"), "{}", warning.msg);
+}
+
+#[wasm_bindgen_test]
+fn malformed_params() {
+    let system = eqs(&[("x + y", "1")]);
+    for params in [
+        JsValue::from(5),
+        JsValue::from_str("y"),
+        object(&[("y", "1")]),
+    ] {
+        let err = solve_linear(system.clone(), params, false, 0).unwrap_err();
+        assert_eq!(err.kind(), "invalid_input");
+    }
+}
+
+#[wasm_bindgen_test]
+fn malformed_initial_values() {
+    let system = eqs(&[("x^2 + y", "3"), ("sin(x)", "y")]);
+    let none = JsValue::UNDEFINED;
+    let err = solve(
+        system.clone(),
+        none.clone(),
+        100,
+        JsValue::from(1),
+        false,
+        0,
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), "invalid_input");
+    let err = solve_homotopy(
+        system.clone(),
+        none.clone(),
+        100,
+        object(&[("z", 1.0)]),
+        false,
+        0,
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), "unknown_variable");
+    let err = sweep(
+        system,
+        none,
+        "y".into(),
+        0.0,
+        1.0,
+        5,
+        100,
+        object(&[("x", "1")]),
+        false,
+        0,
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), "invalid_input");
+}
+
+#[wasm_bindgen_test]
+fn isolate_out_of_range() {
+    let system = eqs(&[("x + y", "1")]);
+    assert_eq!(
+        isolate(system.clone(), 3, "x".into(), false, 0)
+            .unwrap_err()
+            .kind(),
+        "invalid_input"
+    );
+    assert_eq!(
+        isolate(system, 0, "z".into(), false, 0).unwrap_err().kind(),
+        "unknown_variable"
+    );
+}
+
+#[wasm_bindgen_test]
+fn evaluate_garbage() {
+    assert!(evaluate("2 +", JsValue::UNDEFINED, false, 0).is_err());
+    assert!(evaluate("x", JsValue::UNDEFINED, false, 0).is_err());
+    assert!(evaluate("x", JsValue::from(5), false, 0).is_err());
+    let bindings = object(&[("x", "a")]);
+    assert!(evaluate("x", bindings, false, 0).is_err());
+    let err = evaluate("2 [m] + 3 [s]", JsValue::UNDEFINED, false, 0).unwrap_err();
+    assert_eq!(err.kind(), "units");
+}
+
+#[wasm_bindgen_test]
+fn compiled_garbage() {
+    let mut system = CompiledSystem::new(eqs(&[("x^2", "y"), ("x", "2 y")])).unwrap();
+    assert!(system.evaluate(&[1.0]).is_err());
+    assert!(system.jacobian(&[1.0, 0.0, 2.0, 0.0, 3.0]).is_err());
+    assert!(system.solve(JsValue::from_str("fast")).is_err());
+    let options = object(&[("initial", [1.0])]);
+    assert!(system.solve(options).is_err());
+    let options = object(&[("warm_start", "yes")]);
+    assert!(system.solve(options).is_err());
+}
+
+#[wasm_bindgen_test]
+fn variables_in_order() {
+    let names = variables(eqs(&[("2b + sin(a)", "c"), ("a", "d_1 + b")])).unwrap();
+    let names: Vec<String> = names.iter().filter_map(|n| n.as_string()).collect();
+    assert_eq!(names, ["b", "a", "c", "d_1"]);
+    assert!(variables(eqs(&[("sin", "1")])).is_err());
+}
+
+#[wasm_bindgen_test]
+fn solve_with_params() {
+    let system = eqs(&[("m * g", "k * x")]);
+    let params = object(&[("m", 2.0), ("g", 9.81), ("k", 4.0)]);
+    let solution = solve(system, params, 100, JsValue::UNDEFINED, false, 0)
+        .unwrap()
+        .unwrap();
+    let solution: Vec<(String, (f64, f64), String)> = solution
+        .into_iter()
+        .map(|v| serde_wasm_bindgen::from_value(v).unwrap())
+        .collect();
+    assert_eq!(solution.len(), 1);
+    assert_eq!(solution[0].0, "x");
+    assert!((solution[0].1 .0 - 2.0 * 9.81 / 4.0).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test]
+fn solve_like_solve_with() {
+    let named = |solution: Vec<JsValue>| -> Vec<(String, (f64, f64), String)> {
+        solution
+            .into_iter()
+            .map(|v| serde_wasm_bindgen::from_value(v).unwrap())
+            .collect()
+    };
+    // solved directly, giving the solution of least norm rather than one from a random start
+    let line = eqs(&[("x + y", "2")]);
+    for homotopy in [false, true] {
+        let solve = if homotopy { solve_homotopy } else { solve };
+        let solution = solve(
+            line.clone(),
+            JsValue::UNDEFINED,
+            100,
+            JsValue::UNDEFINED,
+            false,
+            0,
+        )
+        .unwrap()
+        .unwrap();
+        for (_, (re, im), _) in named(solution) {
+            assert!((re - 1.0).abs() < 1e-9 && im.abs() < 1e-9);
+        }
+    }
+    let parallel = eqs(&[("x + y", "1"), ("x + y", "2")]);
+    let none = JsValue::UNDEFINED;
+    assert!(
+        solve_homotopy(parallel, none.clone(), 100, none.clone(), false, 0)
+            .unwrap()
+            .is_none()
+    );
+    let solution = solve(
+        eqs(&[("F", "m * a")]),
+        object(&[("m", 2.0), ("a", 3.0)]),
+        100,
+        none,
+        false,
+        0,
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        named(solution),
+        [("F".to_string(), (6.0, 0.0), String::new())]
+    );
+}
+
+#[wasm_bindgen_test]
+fn compiled_system() {
+    let mut system = CompiledSystem::new(eqs(&[("x^2", "y"), ("x", "2 y")])).unwrap();
+    let names: Vec<String> = system
+        .variables()
+        .iter()
+        .filter_map(|n| n.as_string())
+        .collect();
+    assert_eq!(names, ["x", "y"]);
+
+    let rows: Vec<(f64, f64)> = system
+        .evaluate(&[1.0, 0.0, 2.0, 0.0])
+        .unwrap()
+        .into_iter()
+        .map(|v| serde_wasm_bindgen::from_value(v).unwrap())
+        .collect();
+    assert_eq!(rows, [(-1.0, 0.0), (-3.0, 0.0)]);
+    let jacobian: Vec<Vec<(f64, f64)>> = system
+        .jacobian(&[1.0, 0.0, 2.0, 0.0])
+        .unwrap()
+        .into_iter()
+        .map(|v| serde_wasm_bindgen::from_value(v).unwrap())
+        .collect();
+    let expected = [[2.0, -1.0], [1.0, -2.0]];
+    for (row, expected) in jacobian.iter().zip(expected) {
+        for ((re, im), expected) in row.iter().zip(expected) {
+            assert!((re - expected).abs() < 1e-4 && im.abs() < 1e-4);
+        }
+    }
+
+    let solved: Solved = serde_wasm_bindgen::from_value(
+        system
+            .solve(object(&[(
+                "initial",
+                HashMap::from([("x", 1.0), ("y", 0.5)]),
+            )]))
+            .unwrap(),
+    )
+    .unwrap();
+    let solution = solved.solution;
+    assert_eq!(solution[0].0, "x");
+    assert!((solution[0].1 .0 - 0.5).abs() < 1e-9);
+    // the warm start is already on the root
+    let again = system.solve(object(&[("warm_start", true)])).unwrap();
+    assert!(!again.is_undefined());
+    #[derive(Serialize)]
+    struct Options {
+        real_only: bool,
+        attempts: usize,
+    }
+    let options = Options {
+        real_only: true,
+        attempts: 3,
+    };
+    let options = options
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .unwrap();
+    let none = CompiledSystem::new(eqs(&[("x^2", "-1")]))
+        .unwrap()
+        .solve(options)
+        .unwrap();
+    assert!(none.is_undefined());
+}
+
+#[wasm_bindgen_test]
+fn compiled_solution_checks() {
+    let mut system = CompiledSystem::new(eqs(&[("x^2", "2")])).unwrap();
+    #[derive(Serialize)]
+    struct Options {
+        initial: HashMap<&'static str, f64>,
+        refine: bool,
+        certify: bool,
+        verify: bool,
+    }
+    let options = Options {
+        initial: HashMap::from([("x", 1.0)]),
+        refine: true,
+        certify: true,
+        verify: true,
+    };
+    let options = options
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .unwrap();
+    let solved: Solved = serde_wasm_bindgen::from_value(system.solve(options).unwrap()).unwrap();
+    assert_eq!(solved.multiplicity, 1);
+    let precise = solved.precise.unwrap();
+    assert!(precise[0].0.starts_with("1.41421356237309504880168872420"));
+    let [re, im] = solved.enclosure.unwrap()[0];
+    assert!(re.0 <= 2f64.sqrt() && 2f64.sqrt() <= re.1 && im.0 <= 0.0 && 0.0 <= im.1);
+    let verification = solved.verification.unwrap();
+    assert!(verification.residual < 1e-30 && verification.condition < 10.0);
+    assert!(verification.digits[0] >= 30 && !verification.multiple);
+
+    // only what was asked for
+    let solved: Solved =
+        serde_wasm_bindgen::from_value(system.solve(JsValue::UNDEFINED).unwrap()).unwrap();
+    assert!(solved.precise.is_none() && solved.enclosure.is_none());
+    assert!(solved.verification.is_none());
+}